@@ -1,8 +1,7 @@
-import React, {useState, useEffect} from 'react';
+import React, {useState, useEffect, useRef} from 'react';
 import './App.css';
-import { Word } from './Word';
 import { CrosswordGrid } from './Grid';
-import { generate_crossword_js } from './crossword_wasm/crossword'
+import { GeneratorMode, GeneratorOptions, UnplacedReason, WasmCrossword, Word, set_log_level_js } from './crossword_wasm/crossword'
 import { DefinitionArea } from './DefinitionArea';
 import { get_text, TextId, Lang, cycle_lang } from './Text';
 import InputGroup from 'react-bootstrap/InputGroup';
@@ -12,22 +11,67 @@ import Container from 'react-bootstrap/Container';
 import Form from 'react-bootstrap/Form';
 import Stack from 'react-bootstrap/Stack';
 
-type GeneratorMode = "InputOrder" | "Automatic";
+/* Debug a bad layout with e.g. ?log=trace in the URL, the generator then logs to the console. */
+const log_level = new URLSearchParams(window.location.search).get("log")
+if (log_level !== null) {
+  set_log_level_js(log_level)
+}
+
+/* A word that is not on the crossword, and why. */
+interface DroppedWord {
+  word: string;
+  reason: UnplacedReason;
+}
+
+const reason_text_ids: Record<UnplacedReason, TextId> = {
+  [UnplacedReason.AllPositionsConflict]: TextId.ReasonAllPositionsConflict,
+  [UnplacedReason.BlockedByConstraint]: TextId.ReasonBlockedByConstraint,
+  [UnplacedReason.Duplicate]: TextId.ReasonDuplicate,
+  [UnplacedReason.EmptyWord]: TextId.ReasonEmptyWord,
+  [UnplacedReason.InvalidWord]: TextId.ReasonInvalidWord,
+  [UnplacedReason.NoSharedLetters]: TextId.ReasonNoSharedLetters,
+  [UnplacedReason.NotReached]: TextId.ReasonNotReached,
+}
+
+/* The crossword kept between the edits, and the words it was built from. */
+interface CrosswordState {
+  handle: WasmCrossword;
+  words: string[];
+}
 
 function App() {
   const [crosswordWords, setCrosswordWords] = useState<Word[]>([])
   const [isHiddenForPrint, setIsHiddenForPrint] = useState<boolean>(false);
-  const [generatorMode, setGeneratorMode] = useState<GeneratorMode>("InputOrder")
+  const [generatorMode, setGeneratorMode] = useState<GeneratorMode>(GeneratorMode.InputOrder)
   const [lang, setLang] = useState<Lang>("EN")
   const [textInForm, setTextInForm] = useState<string>(get_text(TextId.InitialText, lang))
   const [textUsedForCrossword, setTextUsedForCrossword] = useState<string>(get_text(TextId.InitialText, lang))
-  const [droppedWords, setDroppedWords] = useState<string[]>([])
+  const [droppedWords, setDroppedWords] = useState<DroppedWord[]>([])
+  const [density, setDensity] = useState<number>(0)
+  const [seed, setSeed] = useState<number | undefined>(undefined)
 
+  // A new crossword for every mode and seed. The handle is freed by hand, the garbage collector does not free it.
+  const crossword = useRef<CrosswordState | null>(null)
   useEffect(() => {
-    let {words: cwords, dropped} = generate_crossword_from_input(textUsedForCrossword, generatorMode)
+    const options = new GeneratorOptions()
+    options.seed = seed
+    crossword.current = {handle: new WasmCrossword(generatorMode, options), words: []}
+    return () => {
+      crossword.current?.handle.free()
+      crossword.current = null
+    }
+  }, [generatorMode, seed])
+
+  useEffect(() => {
+    const current = crossword.current
+    if (current === null) {
+      return
+    }
+    let {words: cwords, dropped} = generate_crossword_from_input(current, textUsedForCrossword)
     setCrosswordWords(cwords)
     setDroppedWords(dropped)
-  }, [textUsedForCrossword, generatorMode])
+    setDensity(current.handle.density())
+  }, [generatorMode, seed, textUsedForCrossword])
 
   const onKeyDownInForm = (e: React.KeyboardEvent<HTMLTextAreaElement>) => {
     if (e.key === "Enter" && e.shiftKey === false) {
@@ -51,15 +95,15 @@ function App() {
   }, [isHiddenForPrint, setIsHiddenForPrint]);
 
   const get_next_mode = (prev: GeneratorMode): GeneratorMode => {
-    if (prev === "Automatic") {
-      return "InputOrder"
+    if (prev === GeneratorMode.Automatic) {
+      return GeneratorMode.InputOrder
     }
-    return "Automatic"
+    return GeneratorMode.Automatic
   }
 
-  const mode_to_text: Record<GeneratorMode, string> = {
-    "InputOrder": get_text(TextId.ModeInputOrder, lang),
-    "Automatic": get_text(TextId.ModeAutomatic, lang),
+  const mode_to_text: Partial<Record<GeneratorMode, string>> = {
+    [GeneratorMode.InputOrder]: get_text(TextId.ModeInputOrder, lang),
+    [GeneratorMode.Automatic]: get_text(TextId.ModeAutomatic, lang),
   }
 
   const input_form = (
@@ -85,6 +129,9 @@ function App() {
           }
         </Button>
         <Button id="mode_selector" variant="outline-primary" onClick={() => setGeneratorMode((prev_mode) => get_next_mode(prev_mode))}>{mode_to_text[generatorMode]}</Button>
+        <Button id="shuffle" variant="outline-primary" onClick={() => setSeed(random_seed())}>
+            {get_text(TextId.Shuffle, lang)}
+        </Button>
         <Button id="hide_for_print" variant="outline-primary" value="1" onClick={(_) => setIsHiddenForPrint((prev) => !prev)}>
             {get_text(TextId.HideForPrint, lang)}
         </Button>
@@ -104,7 +151,7 @@ function App() {
     }
     return (<Stack>
         <div>{get_text(TextId.DroppedWords, lang)}</div>
-        {droppedWords.map((w) => <div>{w}</div>)}
+        {droppedWords.map((w) => <div>{w.word}: {get_text(reason_text_ids[w.reason], lang)}</div>)}
     </Stack>)
   })();
 
@@ -127,6 +174,7 @@ function App() {
             <Stack gap={3}>
               <DefinitionArea words={crosswordWords} lang={lang}/>
               {dropped_words_area}
+              <div>{get_text(TextId.Density, lang)}: {Math.round(density * 100)}%</div>
             </Stack>
           </div>
           {footer}
@@ -136,7 +184,9 @@ function App() {
 
 }
 
-const generate_crossword_from_input = (text: string, mode: string) : {words: Word[], dropped: string[]} => {
+const random_seed = (): number => Math.floor(Math.random() * 0xffffffff)
+
+const generate_crossword_from_input = (crossword: CrosswordState, text: string) : {words: Word[], dropped: DroppedWord[]} => {
   const input_definitions: Record<string, string> = text
     .split("\n")
     .map(line => line.trim())
@@ -148,20 +198,26 @@ const generate_crossword_from_input = (text: string, mode: string) : {words: Wor
         return acc
     }, {} as Record<string, string>);
   
-  const output_cwords = generate_crossword_js(Object.keys(input_definitions), mode)
-  const words = output_cwords.map((w) => {
-    return {
-      id: w.id,
-      x: w.x,
-      y: w.y,
-      word: w.word,
-      orientation: w.orientation,
-      definition: input_definitions[w.word],
-    } as Word
-  });
-
-  const output_word_set = new Set(words.map((w) => w.word));
-  const dropped = Object.keys(input_definitions).filter((s) => !output_word_set.has(s));
+  const input_words = Object.keys(input_definitions)
+  // When words are only appended, add them to the existing crossword so the placed words don't move.
+  const is_extension = crossword.words.length > 0 && crossword.words.every((w, i) => input_words[i] === w)
+  // When words are only removed, take them off the crossword so the other words don't move either.
+  const kept_words = crossword.words.filter((w) => input_words.includes(w))
+  const is_removal = kept_words.length < crossword.words.length
+    && kept_words.length === input_words.length
+    && kept_words.every((w, i) => input_words[i] === w)
+  if (is_extension) {
+    input_words.slice(crossword.words.length).forEach((w) => crossword.handle.add_word(w))
+  } else if (is_removal) {
+    crossword.words.filter((w) => !input_words.includes(w)).forEach((w) => crossword.handle.remove_word(w))
+  } else {
+    crossword.handle.generate(input_words)
+  }
+  crossword.words = input_words
+  // The clues live in the crossword, so they go along with it when it is exported.
+  input_words.forEach((w) => crossword.handle.set_clue(w, input_definitions[w]))
+  const words = crossword.handle.words()
+  const dropped = crossword.handle.dropped().map(({word, reason}) => ({word, reason}))
   return {words, dropped}
 }
 
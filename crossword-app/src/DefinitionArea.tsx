@@ -1,5 +1,5 @@
 import React from "react"
-import {Word} from "./Word"
+import { Orientation, Word } from "./crossword_wasm/crossword"
 import Stack from 'react-bootstrap/Stack';
 import { get_text, TextId, Lang } from "./Text";
 
@@ -10,17 +10,17 @@ interface DefinitionAreaProps {
 }
 
 const DefinitionArea: React.FC<DefinitionAreaProps> = ({words, lang}) => {
-    const word_to_div = (w: Word) =><div key={w.id}>{w.id}: {w.definition}</div>;
+    const word_to_div = (w: Word) =><div key={w.id}>{w.id}: {w.clue}</div>;
     words.sort((a, b) => a.id - b.id)
     return (
         <Stack direction="horizontal">
             <Stack>
                 <div>{get_text(TextId.Horizontal, lang)}</div>
-                {words.filter((w) => w.orientation === "hor").map((w) => word_to_div(w))}
+                {words.filter((w) => w.orientation === Orientation.Horiz).map((w) => word_to_div(w))}
             </Stack>
             <Stack>
                 <div>{get_text(TextId.Vertical, lang)}</div>
-                {words.filter((w) => w.orientation === "ver").map((w) => word_to_div(w))}
+                {words.filter((w) => w.orientation === Orientation.Vert).map((w) => word_to_div(w))}
             </Stack>
         </Stack>
     )
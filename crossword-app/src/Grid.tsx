@@ -1,5 +1,5 @@
 import React from "react";
-import { Word } from "./Word";
+import { Orientation, Word } from "./crossword_wasm/crossword";
 
 
 export interface CrosswordGridProps {
@@ -48,7 +48,7 @@ const styles: Record<string, React.CSSProperties> = {
 export const CrosswordGrid: React.FC<CrosswordGridProps> = ({ words, hideLetters=false }) => {
   let maxX = 0, maxY = 0;
   words.forEach(({ x, y, word, orientation }) => {
-    if (orientation === "hor") {
+    if (orientation === Orientation.Horiz) {
       maxX = Math.max(maxX, x + word.length);
       maxY = Math.max(maxY, y + 1);
     } else {
@@ -77,9 +77,9 @@ export const CrosswordGrid: React.FC<CrosswordGridProps> = ({ words, hideLetters
   words.forEach(({ x, y, word, orientation, id }) => {
     for (let i = 0; i < word.length; i++) {
       const grid_id = (i === 0) ? ("" + id) : null; // Id only on first letter.
-      if (orientation === "hor") {
+      if (orientation === Orientation.Horiz) {
         merge_grid_elem({x: x+i, y: y, char: word[i], id: grid_id})
-      } else if (orientation === "ver" ) {
+      } else if (orientation === Orientation.Vert) {
         merge_grid_elem({x, y: y+i, char: word[i], id: grid_id})
       } else {
         throw Error("bad orientation")
@@ -1,4 +1,5 @@
 enum TextId {
+  Density,
   DroppedWords,
   EnterWord,
   Generate,
@@ -8,6 +9,14 @@ enum TextId {
   InitialText,
   ModeAutomatic,
   ModeInputOrder,
+  ReasonAllPositionsConflict,
+  ReasonBlockedByConstraint,
+  ReasonDuplicate,
+  ReasonEmptyWord,
+  ReasonInvalidWord,
+  ReasonNoSharedLetters,
+  ReasonNotReached,
+  Shuffle,
   Vertical,
   Words,
 }
@@ -20,6 +29,7 @@ trap is's a
 `;
 
 const EN: Record<TextId, string> = {
+  [TextId.Density]: "Filled fields",
   [TextId.DroppedWords]: "Dropped words",
   [TextId.EnterWord]: "Enter word, followed by it's definition",
   [TextId.GenerateOnEnter]: "Generate on Enter",
@@ -29,11 +39,20 @@ const EN: Record<TextId, string> = {
   [TextId.InitialText]: initialText,
   [TextId.ModeAutomatic]: "Automatic",
   [TextId.ModeInputOrder]: "Use input order",
+  [TextId.ReasonAllPositionsConflict]: "does not fit anywhere",
+  [TextId.ReasonBlockedByConstraint]: "the crossword would be too large",
+  [TextId.ReasonDuplicate]: "duplicate",
+  [TextId.ReasonEmptyWord]: "empty word",
+  [TextId.ReasonInvalidWord]: "invalid characters",
+  [TextId.ReasonNoSharedLetters]: "no shared letters",
+  [TextId.ReasonNotReached]: "not tried",
+  [TextId.Shuffle]: "Shuffle",
   [TextId.Vertical]: "Vertical",
   [TextId.Words]: "Words",
 };
 
 const PL: Record<TextId, string> = {
+  [TextId.Density]: "Wypełnione pola",
   [TextId.DroppedWords]: "Nie pasujące słowa",
   [TextId.EnterWord]: "Wpisz tekst i definicję",
   [TextId.GenerateOnEnter]: "Generuj na Enter",
@@ -43,6 +62,14 @@ const PL: Record<TextId, string> = {
   [TextId.InitialText]: initialText,
   [TextId.ModeAutomatic]: "Tryb Automatyczny",
   [TextId.ModeInputOrder]: "W kolejności",
+  [TextId.ReasonAllPositionsConflict]: "nigdzie nie pasuje",
+  [TextId.ReasonBlockedByConstraint]: "krzyżówka byłaby za duża",
+  [TextId.ReasonDuplicate]: "powtórzone",
+  [TextId.ReasonEmptyWord]: "puste słowo",
+  [TextId.ReasonInvalidWord]: "niedozwolone znaki",
+  [TextId.ReasonNoSharedLetters]: "brak wspólnych liter",
+  [TextId.ReasonNotReached]: "nie sprawdzone",
+  [TextId.Shuffle]: "Przetasuj",
   [TextId.Vertical]: "Pionowo",
   [TextId.Words]: "Słowa",
 };
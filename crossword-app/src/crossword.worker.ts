@@ -0,0 +1,30 @@
+/* Generates crosswords off the main thread. Post a WorkerRequest to it, it posts back WorkerResponses, see
+ * crossword_worker.rs. Start it with `new Worker(new URL('./crossword.worker.ts', import.meta.url))`. */
+(globalThis as any).FinalizationRegistry = undefined; // The same workaround as in index.tsx.
+// eslint-disable-next-line import/first
+import init_crossword_wasm, { CrosswordWorker } from './crossword_wasm/crossword'
+
+/* How long to generate before reading the next message, e.g. a cancel. */
+const SLICE_MILLIS = 20
+
+const worker = init_crossword_wasm().then(() => new CrosswordWorker((message: any) => postMessage(message)))
+let is_running = false
+
+const run = (w: CrosswordWorker) => {
+  if (w.run(SLICE_MILLIS)) {
+    setTimeout(() => run(w), 0)
+  } else {
+    is_running = false
+  }
+}
+
+onmessage = async (event: MessageEvent) => {
+  const w = await worker
+  w.on_message(event.data)
+  if (!is_running) {
+    is_running = true
+    run(w)
+  }
+}
+
+export {}
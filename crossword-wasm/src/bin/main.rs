@@ -1,23 +1,283 @@
-use std::io::{self, BufRead};
+use std::io;
+use std::process;
+use std::time::Duration;
+
+use crossword::{
+    ASPECT_RATIO_A4_PORTRAIT, ASPECT_RATIO_SQUARE, Annealer, CrosswordGenerator, GeneratorMode,
+    Orientation, Puzzle, SearchBudget, SizeLimit, fitted_compactness_scorer, format_log_record,
+    is_valid_ratio, parse_scorer,
+};
+
+const USAGE: &str = "Usage: main [--mode Automatic|InputOrder|Exhaustive|Beam] [--beam-width K] [--max-nodes N] [--max-millis MS] [--anneal ITERATIONS] [--seed SEED] [--max-width W] [--max-height H] [--aspect-ratio square|a4|RATIO] [--scorer RECIPE] [--log off|error|warn|info|debug|trace] [--trace] [--progress] [--input words|json] [--output text|words|json] < words.txt
+
+Each line of the input is a word, optionally followed by its clue. A line \"word x y hor|ver clue\" pins the word
+at that position and orientation, relative to the other pinned words. Lines \"# title: TITLE\" set the title,
+and likewise author, copyright, date, language and notes. \"--output words\" writes the puzzle in the same
+format.
+
+With \"--input json\" the input is a puzzle JSON, and all its words are pinned. \"--output json\" writes the
+puzzle JSON. JSON needs the \"serde\" feature.
+
+RECIPE is a comma separated list of criteria, best first, each a weighted sum of the scorers crossings, area,
+density, words, aspect_ratio(RATIO) and fitted_area(RATIO), e.g. \"crossings,area\" or \"4*crossings+area\".";
+
+#[cfg(not(feature = "serde"))]
+const NO_SERDE: &str = "JSON needs the crossword built with the \"serde\" feature";
+
+struct Args {
+    mode: GeneratorMode,
+    beam_width: Option<usize>,
+    max_nodes: Option<usize>,
+    max_millis: Option<u64>,
+    anneal_iterations: Option<usize>,
+    seed: Option<u64>,
+    size_limit: SizeLimit,
+    aspect_ratio: Option<f64>,
+    scorer: Option<String>,
+    log_level: log::LevelFilter,
+    trace: bool,
+    progress: bool,
+    json_input: bool,
+    output: Output,
+}
+
+enum Output {
+    /// The grid and the clues, for people.
+    Text,
+    /// The words text, see [Puzzle].
+    Words,
+    Json,
+}
+
+/// Writes the log records to stderr.
+struct StderrLogger;
+
+static STDERR_LOGGER: StderrLogger = StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}", format_log_record(record));
+        }
+    }
+
+    fn flush(&self) {}
+}
 
 fn main() {
-    let input_words = read_non_blank_lines();
-    let words = input_words.iter().map(|w| w.as_str()).collect();
-    let layout =
-        crossword::generator::generate_crossword(&words, crossword::GeneratorMode::InputOrder);
+    let args = parse_args().unwrap_or_else(|err| {
+        eprintln!("{err}\n{USAGE}");
+        process::exit(2);
+    });
+    log::set_logger(&STDERR_LOGGER).unwrap();
+    log::set_max_level(args.log_level);
+    let input = io::read_to_string(io::stdin()).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(1);
+    });
+    let input = if args.json_input {
+        puzzle_from_json(&input).map(|puzzle| (puzzle, Vec::new()))
+    } else {
+        Puzzle::from_words_text(&input)
+    };
+    let (mut puzzle, input_words) = input.unwrap_or_else(|err| {
+        eprintln!("{err}\n{USAGE}");
+        process::exit(2);
+    });
+    let words: Vec<&str> = input_words.iter().map(|w| w.as_str()).collect();
+    let generator = match args.aspect_ratio {
+        Some(target) => CrosswordGenerator::for_mode_with_aspect_ratio(args.mode, target),
+        None => CrosswordGenerator::for_mode(args.mode),
+    };
+    let mut generator = generator
+        .with_seed(args.seed)
+        .with_size_limit(args.size_limit)
+        .with_trace(args.trace);
+    if let Some(recipe) = &args.scorer {
+        generator = generator.with_scorer(parse_scorer(recipe).unwrap());
+    }
+    if let Some(beam_width) = args.beam_width {
+        generator = generator.with_beam_width(beam_width);
+    }
+    if args.progress {
+        generator = generator.with_progress(Box::new(|progress| {
+            eprintln!(
+                "placed {} words, evaluated {} candidates, best score {}",
+                progress.words_placed, progress.candidates_evaluated, progress.best_score
+            );
+        }));
+    }
+    if args.max_nodes.is_some() || args.max_millis.is_some() {
+        generator = generator.with_budget(SearchBudget {
+            max_nodes: args.max_nodes,
+            max_duration: args.max_millis.map(Duration::from_millis),
+        });
+    }
+    let pins = puzzle.layout.get_word_positions();
+    let mut report = generator
+        .generate_pinned_crossword(&words, pins, args.mode)
+        .unwrap_or_else(|err| {
+            eprintln!("{err}");
+            process::exit(1);
+        });
+    if let Some(iterations) = args.anneal_iterations {
+        let mut annealer = Annealer {
+            iterations,
+            seed: args.seed.unwrap_or_default(),
+            size_limit: args.size_limit,
+            pinned: pins.iter().map(|pin| pin.word.clone()).collect(),
+            ..Annealer::default()
+        };
+        if let Some(target) = args.aspect_ratio {
+            annealer.scorer = fitted_compactness_scorer(target);
+        }
+        if let Some(recipe) = &args.scorer {
+            annealer.scorer = parse_scorer(recipe).unwrap();
+        }
+        report.layout = annealer.optimize(&report.layout);
+    }
+    puzzle.layout = report.layout;
+    match args.output {
+        Output::Text => print_puzzle(&puzzle),
+        Output::Words => print!("{}", puzzle.to_words_text()),
+        Output::Json => match puzzle_to_json(&puzzle) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("{err}");
+                process::exit(2);
+            }
+        },
+    }
+    // Keep the words and the JSON on stdout readable by the next command.
+    let print = |line: String| match args.output {
+        Output::Text => println!("{line}"),
+        Output::Words | Output::Json => eprintln!("{line}"),
+    };
+    if !report.unplaced.is_empty() {
+        print("\nDropped words:".to_owned());
+        for unplaced in &report.unplaced {
+            print(format!("  {}: {}", unplaced.word, unplaced.reason));
+        }
+    }
+    if let Some(trace) = &report.trace {
+        print(format!("\nTrace:\n\n{}", trace.to_string().trim_end()));
+    }
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        mode: GeneratorMode::InputOrder,
+        beam_width: None,
+        max_nodes: None,
+        max_millis: None,
+        anneal_iterations: None,
+        seed: None,
+        size_limit: SizeLimit::default(),
+        aspect_ratio: None,
+        scorer: None,
+        log_level: log::LevelFilter::Off,
+        trace: false,
+        progress: false,
+        json_input: false,
+        output: Output::Text,
+    };
+    let mut argv = std::env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        let mut value = || argv.next().ok_or(format!("missing value for {flag}"));
+        match flag.as_str() {
+            "--mode" => args.mode = value()?.parse()?,
+            "--beam-width" => args.beam_width = Some(parse_number(&value()?)?),
+            "--max-nodes" => args.max_nodes = Some(parse_number(&value()?)?),
+            "--max-millis" => args.max_millis = Some(parse_number(&value()?)?),
+            "--anneal" => args.anneal_iterations = Some(parse_number(&value()?)?),
+            "--seed" => args.seed = Some(parse_number(&value()?)?),
+            "--max-width" => args.size_limit.max_width = Some(parse_number(&value()?)?),
+            "--max-height" => args.size_limit.max_height = Some(parse_number(&value()?)?),
+            "--aspect-ratio" => {
+                args.aspect_ratio = Some(match value()?.as_str() {
+                    "square" => ASPECT_RATIO_SQUARE,
+                    "a4" => ASPECT_RATIO_A4_PORTRAIT,
+                    ratio => match parse_number(ratio)? {
+                        ratio if is_valid_ratio(ratio) => ratio,
+                        _ => return Err(format!("the aspect ratio must be positive: {ratio}")),
+                    },
+                })
+            }
+            "--scorer" => {
+                let recipe = value()?;
+                parse_scorer(&recipe)?;
+                args.scorer = Some(recipe);
+            }
+            "--trace" => args.trace = true,
+            "--progress" => args.progress = true,
+            "--input" => {
+                args.json_input = match value()?.as_str() {
+                    "words" => false,
+                    "json" => true,
+                    input => return Err(format!("bad input format: {input}")),
+                }
+            }
+            "--output" => {
+                args.output = match value()?.as_str() {
+                    "text" => Output::Text,
+                    "words" => Output::Words,
+                    "json" => Output::Json,
+                    output => return Err(format!("bad output format: {output}")),
+                }
+            }
+            "--log" => {
+                let level = value()?;
+                args.log_level = level
+                    .parse()
+                    .map_err(|_| format!("bad log level: {level}"))?;
+            }
+            _ => return Err(format!("unknown argument: {flag}")),
+        }
+    }
+    Ok(args)
+}
+
+fn parse_number<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("not a number: {s}"))
+}
+
+fn print_puzzle(puzzle: &Puzzle) {
+    let layout = &puzzle.layout;
+    if let Some(title) = &puzzle.metadata.title {
+        println!("{title}\n");
+    }
     println!("Final:\n\n{:>0}", layout);
+    println!("\nDensity: {:.0}%", layout.density() * 100.0);
+    let mut words = layout.get_words_with_ids();
+    words.sort_by_key(|(_, id)| *id);
+    for (orientation, header) in [(Orientation::Horiz, "Across"), (Orientation::Vert, "Down")] {
+        println!("\n{header}:");
+        for (wp, id) in words.iter().filter(|(wp, _)| wp.orientation == orientation) {
+            println!("  {id}. {}", puzzle.clue(&wp.word).unwrap_or(&wp.word));
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn puzzle_from_json(json: &str) -> Result<Puzzle, String> {
+    crossword::puzzle_from_json(json).map_err(|err| err.to_string())
 }
 
-fn read_non_blank_lines() -> Vec<String> {
-    let stdin = io::stdin();
-    let lines: Vec<String> = stdin
-        .lock()
-        .lines()
-        .filter_map(|line| line.ok())
-        .map(|line| line.trim().to_string())
-        .filter(|line| !line.is_empty())
-        .filter(|line| !line.starts_with("#"))
-        .collect();
+#[cfg(not(feature = "serde"))]
+fn puzzle_from_json(_json: &str) -> Result<Puzzle, String> {
+    Err(NO_SERDE.to_owned())
+}
+
+#[cfg(feature = "serde")]
+fn puzzle_to_json(puzzle: &Puzzle) -> Result<String, String> {
+    Ok(crossword::puzzle_to_json(puzzle))
+}
 
-    lines
+#[cfg(not(feature = "serde"))]
+fn puzzle_to_json(_puzzle: &Puzzle) -> Result<String, String> {
+    Err(NO_SERDE.to_owned())
 }
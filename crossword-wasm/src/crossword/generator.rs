@@ -1,21 +1,41 @@
-use std::cmp;
+use std::cell::Cell;
+use std::cmp::{self, max};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
-use super::Layout;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use wasm_bindgen::prelude::*;
+use web_time::Instant;
 
-fn compare_area(some: &Layout, other: &Layout) -> cmp::Ordering {
-    some.area().cmp(&other.area()).reverse()
-}
-
-fn compare_crossings(some: &Layout, other: &Layout) -> cmp::Ordering {
-    some.crossings_count().cmp(&&other.crossings_count())
-}
+use super::{
+    Area, AspectRatio, Crossings, FittedArea, Layout, LayoutError, Lexicographic, Scorer,
+    SizeLimit, Trace, TraceCandidate, TraceStep, Verdict, WordPosition, validate_word,
+};
 
 pub struct UltimateComparator;
 
 pub struct CrosswordGenerator {
-    comparators: Vec<Box<dyn Fn(&Layout, &Layout) -> cmp::Ordering>>,
+    scorer: Box<dyn Scorer>,
+    /// A cell, so that [CrosswordGenerator::generate_alternatives] can share it among the attempts.
+    budget: Cell<SearchBudget>,
+    beam_width: usize,
+    seed: Option<u64>,
+    size_limit: SizeLimit,
+    record_trace: bool,
+    cancellation: Option<CancellationToken>,
+    on_progress: Option<ProgressFn>,
+    /// How many candidate layouts the current generation evaluated so far.
+    evaluated: Cell<usize>,
 }
 
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GeneratorMode {
     /// Automatic mode generates crossword deterministically regardless of the input order.
     /// Ideally, the crossword would be "optimal" w.r.t. to some measure (it's not, it's greedy).
@@ -23,91 +43,1637 @@ pub enum GeneratorMode {
     /// Build the next state of the Layout based on the order of inputs. This is more useful
     /// for interactive development.
     InputOrder,
+    /// Depth-first backtracking over all the possible placements of all the words, bounded by the
+    /// [SearchBudget]. The best layout places as many words as possible, then has the most crossings, then the
+    /// smallest area. Only feasible for small lists of words, for larger lists it returns the best layout found
+    /// within the budget, which is never worse than the one from [GeneratorMode::Automatic].
+    Exhaustive,
+    /// Beam search: like [GeneratorMode::Automatic], but keeps the best `beam_width` partial layouts at each
+    /// step instead of only one. Slower than greedy, but finds better shapes for lists too long for
+    /// [GeneratorMode::Exhaustive].
+    Beam,
+}
+
+impl FromStr for GeneratorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Automatic" => Ok(GeneratorMode::Automatic),
+            "InputOrder" => Ok(GeneratorMode::InputOrder),
+            "Exhaustive" => Ok(GeneratorMode::Exhaustive),
+            "Beam" => Ok(GeneratorMode::Beam),
+            _ => Err(format!("bad generator mode: {s}")),
+        }
+    }
+}
+
+pub const DEFAULT_BEAM_WIDTH: usize = 8;
+
+/// How many layouts to generate per requested alternative. Many seeds end up with the same layout.
+const ALTERNATIVE_ATTEMPTS_PER_LAYOUT: usize = 4;
+
+/// Limits the work done by the search in [GeneratorMode::Exhaustive]. The search stops at whichever limit is
+/// hit first. With no limits set the search runs until it explores all the layouts.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchBudget {
+    /// How many partial layouts can be visited.
+    pub max_nodes: Option<usize>,
+    /// How long can the search take.
+    pub max_duration: Option<Duration>,
+}
+
+impl SearchBudget {
+    /// The budget of one of `n` searches that together get this budget.
+    fn divided(&self, n: usize) -> SearchBudget {
+        let n = n.max(1);
+        SearchBudget {
+            max_nodes: self.max_nodes.map(|max_nodes| (max_nodes / n).max(1)),
+            max_duration: self.max_duration.map(|d| d / n as u32),
+        }
+    }
+}
+
+impl Default for SearchBudget {
+    fn default() -> Self {
+        SearchBudget {
+            max_nodes: Some(100_000),
+            max_duration: Some(Duration::from_secs(5)),
+        }
+    }
+}
+
+/// Stops a running generation, e.g. from another thread or from the progress callback. The generation then
+/// returns the best layout it found so far.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
 }
 
+/// How far a generation got, see [CrosswordGenerator::with_progress].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    /// Words on the best layout so far.
+    pub words_placed: usize,
+    /// Candidate layouts compared so far.
+    pub candidates_evaluated: usize,
+    /// The score of the best layout so far, see [Scorer::score].
+    pub best_score: f64,
+}
+
+/// Called with the progress of a generation, see [CrosswordGenerator::with_progress].
+pub type ProgressFn = Box<dyn Fn(&Progress)>;
+
+/// How many layouts [GeneratorMode::Exhaustive] visits between the progress reports.
+const PROGRESS_INTERVAL_NODES: usize = 1000;
+
 impl CrosswordGenerator {
-    pub fn new(
-        comparators: Vec<Box<dyn Fn(&Layout, &Layout) -> cmp::Ordering>>,
-    ) -> CrosswordGenerator {
-        CrosswordGenerator { comparators }
+    pub fn new(scorer: Box<dyn Scorer>) -> CrosswordGenerator {
+        CrosswordGenerator {
+            scorer,
+            budget: Cell::new(SearchBudget::default()),
+            beam_width: DEFAULT_BEAM_WIDTH,
+            seed: None,
+            size_limit: SizeLimit::default(),
+            record_trace: false,
+            cancellation: None,
+            on_progress: None,
+            evaluated: Cell::new(0),
+        }
+    }
+
+    pub fn with_budget(mut self, budget: SearchBudget) -> CrosswordGenerator {
+        self.budget = Cell::new(budget);
+        self
+    }
+
+    /// Set how many partial layouts are kept at each step of [GeneratorMode::Beam]. Width of 1 is the same
+    /// as [GeneratorMode::Automatic].
+    pub fn with_beam_width(mut self, beam_width: usize) -> CrosswordGenerator {
+        self.beam_width = max(beam_width, 1);
+        self
+    }
+
+    /// Break ties between equally good candidates randomly instead of always keeping the first one. The same
+    /// seed always gives the same layout, different seeds give alternative layouts.
+    pub fn with_seed(mut self, seed: Option<u64>) -> CrosswordGenerator {
+        self.seed = seed;
+        self
+    }
+
+    /// Rank the candidate layouts with the scorer instead of the one chosen for the mode.
+    pub fn with_scorer(mut self, scorer: Box<dyn Scorer>) -> CrosswordGenerator {
+        self.scorer = scorer;
+        self
+    }
+
+    /// Record how the layout was built in [GenerationReport::trace].
+    pub fn with_trace(mut self, record_trace: bool) -> CrosswordGenerator {
+        self.record_trace = record_trace;
+        self
+    }
+
+    /// Stop the generation when the token is cancelled. The generation returns the best layout so far.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> CrosswordGenerator {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Call the function as the generation goes, after each placed word, or every few thousand layouts in
+    /// [GeneratorMode::Exhaustive]. The last call is with the final layout.
+    pub fn with_progress(mut self, on_progress: ProgressFn) -> CrosswordGenerator {
+        self.on_progress = Some(on_progress);
+        self
+    }
+
+    /// Do not let the layout grow beyond the limit. The words that do not fit are left out.
+    pub fn with_size_limit(mut self, size_limit: SizeLimit) -> CrosswordGenerator {
+        self.size_limit = size_limit;
+        self
+    }
+
+    pub fn generate_crossword(&self, words: &[&str], mode: GeneratorMode) -> GenerationReport {
+        let mut trace = self.record_trace.then(Trace::default);
+        let layout = self.generate_crossword_with_seed(
+            &Layout::new(),
+            words,
+            mode,
+            self.seed,
+            trace.as_mut(),
+        );
+        GenerationReport {
+            trace,
+            cancelled: self.is_cancelled(),
+            ..self.report(layout, words)
+        }
+    }
+
+    /// Generate a crossword where the pinned words keep their orientations and their positions relative to
+    /// each other, and the other words are placed around them. Like every generated layout, the result is
+    /// normalized, so the pinned words are shifted together such that the top left field is 0, 0. The pinned
+    /// words do not need to be in `words`. A pinned word does not need to cross other pinned words, but it must
+    /// not conflict or overlap with them.
+    pub fn generate_pinned_crossword(
+        &self,
+        words: &[&str],
+        pins: &[WordPosition],
+        mode: GeneratorMode,
+    ) -> Result<GenerationReport, PinError> {
+        let (layout, remaining) = self.pin_words(words, pins)?;
+        let all_words = with_pinned_words(words, pins);
+        let mut trace = self.record_trace.then(Trace::default);
+        let layout =
+            self.generate_crossword_with_seed(&layout, &remaining, mode, self.seed, trace.as_mut());
+        Ok(GenerationReport {
+            trace,
+            cancelled: self.is_cancelled(),
+            ..self.report(layout, &all_words)
+        })
+    }
+
+    /// Generate the crossword one word at a time, see [GenerationSteps].
+    pub fn into_steps(self, words: &[&str], mode: GeneratorMode) -> GenerationSteps {
+        GenerationSteps::new(self, Layout::new(), words, words, mode)
+    }
+
+    /// Like [CrosswordGenerator::into_steps], around the pinned words, see
+    /// [CrosswordGenerator::generate_pinned_crossword].
+    pub fn into_pinned_steps(
+        self,
+        words: &[&str],
+        pins: &[WordPosition],
+        mode: GeneratorMode,
+    ) -> Result<GenerationSteps, PinError> {
+        let (layout, remaining) = self.pin_words(words, pins)?;
+        let all_words = with_pinned_words(words, pins);
+        Ok(GenerationSteps::new(
+            self, layout, &remaining, &all_words, mode,
+        ))
     }
 
-    pub fn generate_crossword(&self, words: &Vec<&str>, mode: GeneratorMode) -> Layout {
+    /// Put the pinned words on an empty layout. Return the layout and the words that are not pinned.
+    fn pin_words<'a>(
+        &self,
+        words: &[&'a str],
+        pins: &[WordPosition],
+    ) -> Result<(Layout, Vec<&'a str>), PinError> {
         let mut layout = Layout::new();
-        let mut words = words.clone();
-        if let GeneratorMode::Automatic = mode {
-            // Place larger first. Otherwise one will end up with a tiny shape that cannot be extended.
-            words.sort_by(|a, b| b.len().cmp(&a.len()));
-        }
-
-        while !words.is_empty() {
-            // eprintln!("Now word: {word}");
-            let mut best_layout_with_word: Option<(Layout, usize)> = None;
-            let word_iter: Box<dyn Iterator<Item = &&str>> = match mode {
-                GeneratorMode::Automatic => Box::new(words.iter()),
-                GeneratorMode::InputOrder => Box::new(words.iter().take(1)),
+        let mut words = words.to_vec();
+        for pin in pins {
+            if let Some(error) = layout.find_placement_conflict(&pin.word, pin.pos, pin.orientation)
+            {
+                return Err(PinError {
+                    pin: pin.clone(),
+                    error,
+                });
+            }
+            if !self
+                .size_limit
+                .allows(layout.dimensions_with(&pin.word, pin.pos, pin.orientation))
+            {
+                return Err(PinError {
+                    pin: pin.clone(),
+                    error: LayoutError::ExceedsSizeLimit,
+                });
+            }
+            if let Err(error) = layout.insert_at(&pin.word, pin.pos, pin.orientation) {
+                return Err(PinError {
+                    pin: pin.clone(),
+                    error,
+                });
+            }
+            if let Some(i) = words.iter().position(|w| *w == pin.word) {
+                words.remove(i);
+            }
+        }
+        Ok((layout, words))
+    }
+
+    /// Tell which of the words are not on the layout, and why. A word given more than once is placed once,
+    /// the other copies are [UnplacedReason::Duplicate].
+    pub fn report(&self, layout: Layout, words: &[&str]) -> GenerationReport {
+        let mut unplaced: Vec<UnplacedWord> = Vec::new();
+        let placed = layout.words();
+        for word in unplaced_words(words, &layout) {
+            let reason = if let Err(err) = validate_word(word) {
+                match err {
+                    LayoutError::EmptyWord => UnplacedReason::EmptyWord,
+                    _ => UnplacedReason::InvalidWord,
+                }
+            } else if placed.contains(&word) || unplaced.iter().any(|u| u.word == word) {
+                UnplacedReason::Duplicate
+            } else if !word.chars().any(|c| layout.has_char(c)) && !placed.is_empty() {
+                UnplacedReason::NoSharedLetters
+            } else if layout.get_possible_start_positions(word).is_empty() {
+                UnplacedReason::AllPositionsConflict
+            } else if layout
+                .get_possible_start_positions_within(word, &self.size_limit)
+                .is_empty()
+            {
+                UnplacedReason::BlockedByConstraint
+            } else {
+                UnplacedReason::NotReached
             };
-            for (i_word, word) in word_iter.enumerate() {
-                eprintln!("now try word {word}");
-                for (pos, orientation) in layout.get_possible_start_positions(word) {
-                    eprintln!("Now trying {word:?} {pos} {orientation}");
-                    let mut updated_layout = layout.clone();
-                    updated_layout.insert_at(word, pos, orientation).unwrap();
-                    best_layout_with_word = if let Some((best_layout, best_i_word)) =
-                        best_layout_with_word
-                    {
+            unplaced.push(UnplacedWord {
+                word: word.to_owned(),
+                reason,
+            });
+        }
+        GenerationReport {
+            layout,
+            unplaced,
+            trace: None,
+            cancelled: false,
+        }
+    }
+
+    /// Generate a crossword by adding the words to the given layout.
+    fn generate_crossword_with_seed(
+        &self,
+        layout: &Layout,
+        words: &[&str],
+        mode: GeneratorMode,
+        seed: Option<u64>,
+        trace: Option<&mut Trace>,
+    ) -> Layout {
+        let words = &distinct_words(layout, words);
+        let started = Instant::now();
+        self.evaluated.set(0);
+        let generated = match mode {
+            GeneratorMode::Automatic | GeneratorMode::InputOrder => {
+                self.generate_greedy(layout, words, mode, seed, trace)
+            }
+            GeneratorMode::Exhaustive => {
+                let generated = self.generate_exhaustive(layout, words, seed);
+                if let Some(trace) = trace {
+                    *trace = Trace::from_layout(&generated, layout.get_word_positions().len());
+                }
+                generated
+            }
+            GeneratorMode::Beam => {
+                let generated = self.generate_beam(layout, words, seed);
+                if let Some(trace) = trace {
+                    *trace = Trace::from_layout(&generated, layout.get_word_positions().len());
+                }
+                generated
+            }
+        };
+        log::info!(
+            mode:?,
+            words = words.len(),
+            placed = generated.get_word_positions().len(),
+            crossings = generated.crossings_count(),
+            area = generated.area(),
+            elapsed:? = started.elapsed();
+            "generated crossword"
+        );
+        if self.is_cancelled() {
+            log::info!("generation cancelled");
+        }
+        self.report_progress(&generated);
+        generated
+    }
+
+    /// Generate up to `count` different layouts, best first. The candidates are the layout from
+    /// [CrosswordGenerator::generate_crossword] and the layouts generated with other seeds. A layout is
+    /// kept only if its [Layout::distance] to every better layout is at least `min_distance`, so 0.0 only drops
+    /// the layouts that are the same up to translation or transposition. All of them have the pinned words,
+    /// see [CrosswordGenerator::generate_pinned_crossword].
+    ///
+    /// Each of the `count` layouts takes a few generations. In [GeneratorMode::Exhaustive] they share the
+    /// [SearchBudget], so all of them together take as long as a single layout.
+    pub fn generate_alternatives(
+        &self,
+        words: &[&str],
+        pins: &[WordPosition],
+        mode: GeneratorMode,
+        count: usize,
+        min_distance: f64,
+    ) -> Result<Vec<Layout>, PinError> {
+        let (pinned, remaining) = self.pin_words(words, pins)?;
+        let n_attempts = count * ALTERNATIVE_ATTEMPTS_PER_LAYOUT;
+        let budget = self.budget.get();
+        self.budget.set(budget.divided(n_attempts));
+        let base_seed = self.seed.unwrap_or_default();
+        // The first one is the layout of generate_pinned_crossword.
+        let mut candidates: Vec<Layout> = (0..n_attempts as u64)
+            .map(|i| {
+                let seed = if i == 0 {
+                    self.seed
+                } else {
+                    Some(base_seed.wrapping_add(i))
+                };
+                self.generate_crossword_with_seed(&pinned, &remaining, mode, seed, None)
+            })
+            .collect();
+        self.budget.set(budget);
+        // Stable sort, so the first layout stays first among the equal ones.
+        candidates.sort_by(|a, b| {
+            let by_words = a
+                .get_word_positions()
+                .len()
+                .cmp(&b.get_word_positions().len());
+            by_words.then_with(|| self.compare(a, b)).reverse()
+        });
+
+        let mut alternatives: Vec<Layout> = Vec::new();
+        for candidate in candidates {
+            if alternatives.len() == count {
+                break;
+            }
+            let is_distinct = alternatives.iter().all(|a| {
+                let distance = a.distance(&candidate);
+                distance > 0.0 && distance >= min_distance
+            });
+            if is_distinct {
+                alternatives.push(candidate);
+            }
+        }
+        Ok(alternatives)
+    }
+
+    /// Add a word to an existing layout without moving the words that are already placed. The word goes to the
+    /// best of its possible positions. Return None if the word does not fit anywhere, or is already on the
+    /// layout.
+    pub fn insert_word(&self, layout: &Layout, word: &str) -> Option<Layout> {
+        if distinct_words(layout, &[word]).is_empty() {
+            return None;
+        }
+        let mut tie_breaker = TieBreaker::new(self.seed);
+        let mut best_layout: Option<Layout> = None;
+        let mut n_equal: usize = 0;
+        for (pos, orientation) in layout.get_possible_start_positions_within(word, &self.size_limit)
+        {
+            let mut updated_layout = layout.clone();
+            if updated_layout.insert_at(word, pos, orientation).is_err() {
+                continue;
+            }
+            let ordering = match &best_layout {
+                Some(best_layout) => self.compare(&updated_layout, best_layout),
+                None => cmp::Ordering::Greater,
+            };
+            let is_better = match ordering {
+                cmp::Ordering::Greater => {
+                    n_equal = 1;
+                    true
+                }
+                cmp::Ordering::Equal => {
+                    n_equal += 1;
+                    tie_breaker.replace_equal(n_equal)
+                }
+                cmp::Ordering::Less => false,
+            };
+            if is_better {
+                best_layout = Some(updated_layout);
+            }
+        }
+        best_layout.map(|layout| layout.normalize())
+    }
+
+    /// Add a word to an existing layout like [CrosswordGenerator::insert_word]. If the word does not fit,
+    /// generate the whole layout again with all the words, and the pinned words where they were pinned, see
+    /// [CrosswordGenerator::generate_pinned_crossword]. A word that is already on the layout is skipped.
+    ///
+    /// # Returns
+    /// The new layout, and true if the word was added, or skipped, without moving the other words.
+    pub fn extend_crossword(
+        &self,
+        layout: &Layout,
+        word: &str,
+        pins: &[WordPosition],
+        mode: GeneratorMode,
+    ) -> Result<(Layout, bool), PinError> {
+        if distinct_words(layout, &[word]).is_empty() {
+            log::info!(word; "the word is already on the layout");
+            return Ok((layout.clone(), true));
+        }
+        if let Some(extended) = self.insert_word(layout, word) {
+            return Ok((extended, true));
+        }
+        log::info!(word; "cannot add the word to the layout, generate it again");
+        let mut words = layout.words();
+        words.push(word);
+        let report = self.generate_pinned_crossword(&words, pins, mode)?;
+        Ok((report.layout, false))
+    }
+
+    fn generate_greedy(
+        &self,
+        layout: &Layout,
+        words: &[&str],
+        mode: GeneratorMode,
+        seed: Option<u64>,
+        mut trace: Option<&mut Trace>,
+    ) -> Layout {
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.initial = layout.get_word_positions().clone();
+        }
+        let mut layout = layout.clone();
+        let mut words = words.to_vec();
+        sort_for_mode(&mut words, mode);
+
+        let mut tie_breaker = TieBreaker::new(seed);
+        while !words.is_empty() && !self.is_cancelled() {
+            let mut candidates: Vec<TraceCandidate> = Vec::new();
+            let recorded = trace.is_some().then_some(&mut candidates);
+            if let Some((best_layout, best_i_word)) =
+                self.greedy_step(&layout, &words, mode, &mut tie_breaker, recorded)
+            {
+                if let Some(trace) = trace.as_deref_mut() {
+                    trace.steps.push(TraceStep {
+                        candidates,
+                        placement: best_layout.get_word_positions().last().unwrap().clone(),
+                    });
+                }
+                layout = best_layout;
+                words.remove(best_i_word);
+                self.report_progress(&layout);
+            } else {
+                log::info!(words:?; "failed to insert words");
+                break;
+            }
+        }
+        layout.normalize()
+    }
+
+    /// Place the best of the words at its best position. Return the new layout and the index of the placed
+    /// word, or None if none of the words fits. The compared candidates are added to `candidates`.
+    fn greedy_step(
+        &self,
+        layout: &Layout,
+        words: &[&str],
+        mode: GeneratorMode,
+        tie_breaker: &mut TieBreaker,
+        mut candidates: Option<&mut Vec<TraceCandidate>>,
+    ) -> Option<(Layout, usize)> {
+        let mut best_layout_with_word: Option<(Layout, usize)> = None;
+        // How many candidates are as good as the best one so far.
+        let mut n_equal: usize = 0;
+        let word_iter: Box<dyn Iterator<Item = &&str>> = match mode {
+            GeneratorMode::InputOrder => Box::new(words.iter().take(1)),
+            _ => Box::new(words.iter()),
+        };
+        for (i_word, word) in word_iter.enumerate() {
+            for (pos, orientation) in
+                layout.get_possible_start_positions_within(word, &self.size_limit)
+            {
+                log::trace!(word, pos:%, orientation:%; "candidate");
+                self.evaluated.set(self.evaluated.get() + 1);
+                let mut updated_layout = layout.clone();
+                if updated_layout.insert_at(word, pos, orientation).is_err() {
+                    continue;
+                }
+                best_layout_with_word =
+                    if let Some((best_layout, best_i_word)) = best_layout_with_word {
                         // Try if this results in better layout:
-                        // - Choosing random among equal
                         // - Use different metric, e.g. how "squarish" the layout is
-                        if self.compare(&updated_layout, &best_layout) == cmp::Ordering::Greater {
-                            eprintln!(
-                                "Tried this word {word:?} and is better {pos} {orientation}"
-                            );
-                            //eprintln!("Before\n{}\n", best_layout);
-                            //eprintln!("Updated:\n{}\n", updated_layout);
+                        let ordering = self.compare(&updated_layout, &best_layout);
+                        let is_better = match ordering {
+                            cmp::Ordering::Greater => {
+                                n_equal = 1;
+                                true
+                            }
+                            cmp::Ordering::Equal => {
+                                n_equal += 1;
+                                tie_breaker.replace_equal(n_equal)
+                            }
+                            cmp::Ordering::Less => false,
+                        };
+                        if let Some(candidates) = candidates.as_deref_mut() {
+                            let verdict = match ordering {
+                                cmp::Ordering::Greater => Verdict::Better,
+                                cmp::Ordering::Equal => Verdict::Equal,
+                                cmp::Ordering::Less => Verdict::Worse,
+                            };
+                            candidates.push(TraceCandidate {
+                                word: word.to_string(),
+                                pos,
+                                orientation,
+                                verdict,
+                            });
+                        }
+                        if is_better {
+                            log::trace!(word, pos:%, orientation:%; "better candidate");
                             Some((updated_layout, i_word))
                         } else {
-                            // eprintln!("Tried this and is same or worse:\n{}", updated_layout);
                             Some((best_layout, best_i_word))
                         }
                     } else {
-                        eprintln!(
-                            "This is the best because no other candidate pos={pos} or={orientation}:\n{}",
-                            updated_layout
-                        );
+                        log::trace!(word, pos:%, orientation:%; "first candidate");
+                        if let Some(candidates) = candidates.as_deref_mut() {
+                            candidates.push(TraceCandidate {
+                                word: word.to_string(),
+                                pos,
+                                orientation,
+                                verdict: Verdict::First,
+                            });
+                        }
+                        n_equal = 1;
                         Some((updated_layout, i_word))
                     };
+            }
+        }
+        best_layout_with_word
+    }
+
+    fn generate_exhaustive(&self, layout: &Layout, words: &[&str], seed: Option<u64>) -> Layout {
+        // Start from the greedy layout so the search has something to beat, and returns something sensible
+        // even if the budget runs out early.
+        let greedy = self.generate_greedy(layout, words, GeneratorMode::Automatic, seed, None);
+        let mut words = words.to_vec();
+        words.sort_by_key(|w| cmp::Reverse(w.len()));
+        let mut search = ExhaustiveSearch {
+            generator: self,
+            words: &words,
+            budget: self.budget.get(),
+            size_limit: self.size_limit,
+            started: Instant::now(),
+            n_nodes: 0,
+            visited: HashSet::new(),
+            tie_breaker: TieBreaker::new(seed),
+            is_default_scorer: self.scorer.recipe()
+                == default_scorer(GeneratorMode::Exhaustive).recipe(),
+            best_score: ExhaustiveSearch::score(&greedy),
+            best: greedy,
+        };
+        let remaining: Vec<usize> = (0..words.len()).collect();
+        search.visit(layout.clone(), &remaining);
+        log::info!(
+            nodes = search.n_nodes,
+            elapsed:? = search.started.elapsed();
+            "exhaustive search finished"
+        );
+        search.best.normalize()
+    }
+
+    fn generate_beam(&self, layout: &Layout, words: &[&str], seed: Option<u64>) -> Layout {
+        let mut words = words.to_vec();
+        words.sort_by_key(|w| cmp::Reverse(w.len()));
+        // Each state in the beam is a partial layout and the words that are not placed yet.
+        let mut beam: Vec<(Layout, Vec<&str>)> = vec![(layout.clone(), words)];
+        let mut tie_breaker = TieBreaker::new(seed);
+        while !self.is_cancelled() {
+            let mut candidates: Vec<(Layout, Vec<&str>)> = Vec::new();
+            for (layout, words) in &beam {
+                for (i_word, word) in words.iter().enumerate() {
+                    for (pos, orientation) in
+                        layout.get_possible_start_positions_within(word, &self.size_limit)
+                    {
+                        let mut updated_layout = layout.clone();
+                        if updated_layout.insert_at(word, pos, orientation).is_err() {
+                            continue;
+                        }
+                        let mut remaining = words.clone();
+                        remaining.remove(i_word);
+                        candidates.push((updated_layout, remaining));
+                        self.evaluated.set(self.evaluated.get() + 1);
+                    }
                 }
             }
-            if let Some((best_layout, best_i_word)) = best_layout_with_word {
-                layout = best_layout;
-                words.remove(best_i_word);
-            } else {
-                eprintln!("Failed to insert words: {words:?}");
+            if candidates.is_empty() {
                 break;
             }
+            tie_breaker.shuffle(&mut candidates);
+            // Stable sort, so among equal candidates the earlier ones are kept, like in the greedy mode. With a
+            // seed, the earlier ones are random.
+            candidates.sort_by(|(a, _), (b, _)| self.compare(b, a));
+            // Different states often lead to the same layout, keep only one of them so the beam stays diverse.
+            let mut seen = HashSet::new();
+            candidates.retain(|(layout, remaining)| {
+                seen.insert((format!("{layout}"), remaining.clone()))
+            });
+            candidates.truncate(self.beam_width);
+            beam = candidates;
+            self.report_progress(&beam[0].0);
         }
-        layout.normalize()
+        let (best_layout, remaining) = beam.swap_remove(0);
+        if !remaining.is_empty() {
+            log::info!(words:? = remaining; "failed to insert words");
+        }
+        best_layout.normalize()
     }
 
     fn compare(&self, candidate: &Layout, current_best: &Layout) -> cmp::Ordering {
-        for comparator in &self.comparators {
-            match comparator(candidate, current_best) {
-                cmp::Ordering::Less => return cmp::Ordering::Less,
-                cmp::Ordering::Equal => (),
-                cmp::Ordering::Greater => return cmp::Ordering::Greater,
+        self.scorer.compare(candidate, current_best)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+    }
+
+    fn report_progress(&self, best: &Layout) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(&Progress {
+                words_placed: best.get_word_positions().len(),
+                candidates_evaluated: self.evaluated.get(),
+                best_score: self.scorer.score(best),
+            });
+        }
+    }
+}
+
+/// The generated layout, and the words that did not make it.
+#[derive(Clone, Debug)]
+pub struct GenerationReport {
+    pub layout: Layout,
+    /// In the order of the input words.
+    pub unplaced: Vec<UnplacedWord>,
+    /// How the layout was built, if asked for with [CrosswordGenerator::with_trace].
+    pub trace: Option<Trace>,
+    /// The generation was stopped with [CrosswordGenerator::with_cancellation], the layout is the best one
+    /// found until then.
+    pub cancelled: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnplacedWord {
+    pub word: String,
+    pub reason: UnplacedReason,
+}
+
+/// Why a word is not on the layout. The reason is worked out on the final layout, so it tells why the word
+/// cannot be added now, not why the generator skipped it when the layout was smaller.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnplacedReason {
+    /// None of the letters of the word is on the layout.
+    NoSharedLetters,
+    /// The word shares letters with the layout, but every position where it would cross other word conflicts
+    /// with other letters or touches other word.
+    AllPositionsConflict,
+    /// The word has positions, but all of them would break the [SizeLimit].
+    BlockedByConstraint,
+    /// The same word is already on the layout.
+    Duplicate,
+    /// The word fits now, but the generator did not get to it, e.g. the search budget ran out, or an earlier
+    /// word did not fit in [GeneratorMode::InputOrder].
+    NotReached,
+    /// The word has characters that cannot be on the layout, e.g. a space.
+    InvalidWord,
+    /// The word is empty.
+    EmptyWord,
+}
+
+impl fmt::Display for UnplacedReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnplacedReason::NoSharedLetters => write!(f, "no shared letters with the crossword"),
+            UnplacedReason::AllPositionsConflict => write!(f, "every position conflicts"),
+            UnplacedReason::BlockedByConstraint => write!(f, "the crossword would be too large"),
+            UnplacedReason::Duplicate => write!(f, "duplicate"),
+            UnplacedReason::NotReached => write!(f, "not tried"),
+            UnplacedReason::InvalidWord => write!(f, "invalid characters"),
+            UnplacedReason::EmptyWord => write!(f, "empty word"),
+        }
+    }
+}
+
+/// A pinned word that cannot be put where it was pinned because of the words pinned before it.
+#[derive(Clone, Debug)]
+pub struct PinError {
+    pub pin: WordPosition,
+    pub error: LayoutError,
+}
+
+impl fmt::Display for PinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot pin {:?} at {} {}: {}",
+            self.pin.word, self.pin.pos, self.pin.orientation, self.error
+        )
+    }
+}
+
+impl std::error::Error for PinError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Generates a crossword one word at a time. Each step places one word and gives the layout so far, the
+/// last step gives the same layout as [CrosswordGenerator::generate_crossword]. This lets the caller
+/// show the layout as it grows, or spread the work over many short calls, e.g. over the animation frames in
+/// the browser.
+///
+/// [GeneratorMode::Beam] and [GeneratorMode::Exhaustive] do not build the layout word by word, so their
+/// first step runs the whole search, and the steps replay the placements of the found layout.
+///
+/// The steps honor [CrosswordGenerator::with_cancellation] and [CrosswordGenerator::with_progress] like the
+/// other ways to generate.
+pub struct GenerationSteps {
+    generator: CrosswordGenerator,
+    mode: GeneratorMode,
+    /// Not normalized, so the replayed placements keep their positions.
+    layout: Layout,
+    /// All the input words, for the report.
+    words: Vec<String>,
+    state: StepsState,
+    tie_breaker: TieBreaker,
+    trace: Option<Trace>,
+}
+
+enum StepsState {
+    /// The words that are not placed yet.
+    Greedy(Vec<String>),
+    /// The words to search the layout for, on the first step.
+    Search(Vec<String>),
+    /// The placements of the searched layout that are not replayed yet.
+    Replay(std::vec::IntoIter<WordPosition>),
+    Done,
+}
+
+impl GenerationSteps {
+    fn new(
+        generator: CrosswordGenerator,
+        layout: Layout,
+        words: &[&str],
+        all_words: &[&str],
+        mode: GeneratorMode,
+    ) -> GenerationSteps {
+        let mut words = distinct_words(&layout, words);
+        let owned = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+        let state = match mode {
+            GeneratorMode::Automatic | GeneratorMode::InputOrder => {
+                sort_for_mode(&mut words, mode);
+                StepsState::Greedy(owned(&words))
+            }
+            GeneratorMode::Beam | GeneratorMode::Exhaustive => StepsState::Search(owned(&words)),
+        };
+        let trace = generator.record_trace.then(|| Trace {
+            initial: layout.get_word_positions().clone(),
+            steps: Vec::new(),
+        });
+        GenerationSteps {
+            tie_breaker: TieBreaker::new(generator.seed),
+            generator,
+            mode,
+            layout,
+            words: owned(all_words),
+            state,
+            trace,
+        }
+    }
+
+    /// The layout so far.
+    pub fn layout(&self) -> Layout {
+        self.layout.clone().normalize()
+    }
+
+    /// The layout so far, and the words that are not on it yet. The words that the generator did not get to
+    /// are [UnplacedReason::NotReached].
+    pub fn report(&self) -> GenerationReport {
+        let words: Vec<&str> = self.words.iter().map(|w| w.as_str()).collect();
+        GenerationReport {
+            trace: self.trace.clone(),
+            ..self.generator.report(self.layout(), &words)
+        }
+    }
+}
+
+impl Iterator for GenerationSteps {
+    type Item = Layout;
+
+    fn next(&mut self) -> Option<Layout> {
+        match &mut self.state {
+            StepsState::Greedy(words) => {
+                if words.is_empty() || self.generator.is_cancelled() {
+                    self.state = StepsState::Done;
+                    return None;
+                }
+                let word_refs: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+                let mut candidates: Vec<TraceCandidate> = Vec::new();
+                let recorded = self.trace.is_some().then_some(&mut candidates);
+                let step = self.generator.greedy_step(
+                    &self.layout,
+                    &word_refs,
+                    self.mode,
+                    &mut self.tie_breaker,
+                    recorded,
+                );
+                let Some((layout, i_word)) = step else {
+                    log::info!(words:?; "failed to insert words");
+                    self.state = StepsState::Done;
+                    return None;
+                };
+                if let Some(trace) = &mut self.trace {
+                    trace.steps.push(TraceStep {
+                        candidates,
+                        placement: layout.get_word_positions().last().unwrap().clone(),
+                    });
+                }
+                self.layout = layout;
+                words.remove(i_word);
+                self.generator.report_progress(&self.layout);
+            }
+            StepsState::Search(words) => {
+                let word_refs: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+                let generated = self.generator.generate_crossword_with_seed(
+                    &self.layout,
+                    &word_refs,
+                    self.mode,
+                    self.generator.seed,
+                    None,
+                );
+                let n_initial = self.layout.get_word_positions().len();
+                let mut placements = generated.get_word_positions().clone();
+                let replayed = placements.split_off(n_initial);
+                // The generated layout is normalized, so start again from its initial words.
+                let mut layout = Layout::new();
+                for wp in &placements {
+                    layout.insert_at(&wp.word, wp.pos, wp.orientation).unwrap();
+                }
+                self.layout = layout;
+                if self.trace.is_some() {
+                    self.trace = Some(Trace::from_layout(&generated, n_initial));
+                }
+                self.state = StepsState::Replay(replayed.into_iter());
+                return self.next();
+            }
+            StepsState::Replay(placements) => {
+                let Some(wp) = placements.next() else {
+                    self.state = StepsState::Done;
+                    return None;
+                };
+                self.layout
+                    .insert_at(&wp.word, wp.pos, wp.orientation)
+                    .unwrap();
+            }
+            StepsState::Done => return None,
+        }
+        Some(self.layout())
+    }
+}
+
+/// Chooses among the equally good candidates. Without a seed it always keeps the first candidate.
+struct TieBreaker {
+    rng: Option<ChaCha8Rng>,
+}
+
+impl TieBreaker {
+    fn new(seed: Option<u64>) -> TieBreaker {
+        TieBreaker {
+            rng: seed.map(ChaCha8Rng::seed_from_u64),
+        }
+    }
+
+    /// Tell if the n-th equal candidate should replace the chosen one. Replacing with probability 1/n makes
+    /// each of the equal candidates equally likely to be chosen in the end.
+    fn replace_equal(&mut self, n_equal: usize) -> bool {
+        match &mut self.rng {
+            Some(rng) => rng.gen_range(0..n_equal) == 0,
+            None => false,
+        }
+    }
+
+    /// Shuffle the candidates before a stable sort, so the order among the equal ones is random.
+    fn shuffle<T>(&mut self, candidates: &mut [T]) {
+        if let Some(rng) = &mut self.rng {
+            candidates.shuffle(rng);
+        }
+    }
+}
+
+/// Score of a layout in the exhaustive search: placed words, crossings and area. Larger is better. It orders
+/// the layouts to visit, and bounds the search with the default scorer. The best layout has the most words,
+/// then is the best for the scorer of the generator.
+type ExhaustiveScore = (usize, usize, cmp::Reverse<u32>);
+
+struct ExhaustiveSearch<'a> {
+    generator: &'a CrosswordGenerator,
+    words: &'a [&'a str],
+    budget: SearchBudget,
+    size_limit: SizeLimit,
+    started: Instant,
+    n_nodes: usize,
+    /// Layouts already explored, as the printed grid plus the indices of the words that are not placed yet.
+    /// The same layout is reached by inserting the same words in different order.
+    visited: HashSet<(String, Vec<usize>)>,
+    tie_breaker: TieBreaker,
+    /// The scorer ranks as [ExhaustiveScore] does, so the crossings and the area bound the search too.
+    is_default_scorer: bool,
+    best: Layout,
+    best_score: ExhaustiveScore,
+}
+
+impl ExhaustiveSearch<'_> {
+    fn score(layout: &Layout) -> ExhaustiveScore {
+        (
+            layout.get_word_positions().len(),
+            layout.crossings_count(),
+            cmp::Reverse(layout.area()),
+        )
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.generator.is_cancelled()
+            || self.budget.max_nodes.is_some_and(|n| self.n_nodes >= n)
+            || self
+                .budget
+                .max_duration
+                .is_some_and(|d| self.started.elapsed() >= d)
+    }
+
+    /// Visit the layout and recursively all the layouts that extend it with one of the remaining words.
+    fn visit(&mut self, layout: Layout, remaining: &[usize]) {
+        if self.is_exhausted() {
+            return;
+        }
+        self.n_nodes += 1;
+        let evaluated = &self.generator.evaluated;
+        evaluated.set(evaluated.get() + 1);
+        if self.n_nodes.is_multiple_of(PROGRESS_INTERVAL_NODES) {
+            self.generator.report_progress(&self.best);
+        }
+        if !self
+            .visited
+            .insert((format!("{layout}"), remaining.to_vec()))
+        {
+            return;
+        }
+
+        let score = Self::score(&layout);
+        let ordering = score
+            .0
+            .cmp(&self.best_score.0)
+            .then_with(|| self.generator.compare(&layout, &self.best));
+        if ordering == cmp::Ordering::Greater {
+            self.best_score = score;
+            self.best = layout.clone();
+        }
+
+        // Branch and bound. The area never shrinks when adding words, and each word adds at most as many
+        // crossings as it has letters. If even that cannot beat the best layout, do not go deeper. Any other
+        // scorer can prefer a layout with more words only.
+        let optimistic: ExhaustiveScore = (
+            score.0 + remaining.len(),
+            score.1
+                + remaining
+                    .iter()
+                    .map(|&i| self.words[i].len())
+                    .sum::<usize>(),
+            score.2,
+        );
+        if self.is_default_scorer && optimistic <= self.best_score
+            || optimistic.0 < self.best_score.0
+        {
+            return;
+        }
+
+        let mut children: Vec<(Layout, Vec<usize>)> = Vec::new();
+        for (k, &i_word) in remaining.iter().enumerate() {
+            let word = self.words[i_word];
+            let mut positions = layout.get_possible_start_positions_within(word, &self.size_limit);
+            if layout.get_word_positions().is_empty() {
+                // Transposed layouts are equally good, the first word can be horizontal.
+                positions.truncate(1);
+            }
+            let mut seen = HashSet::new();
+            positions.retain(|p| seen.insert(*p));
+
+            let mut child_remaining = remaining.to_vec();
+            child_remaining.remove(k);
+            for (pos, orientation) in positions {
+                let mut child = layout.clone();
+                if child.insert_at(word, pos, orientation).is_err() {
+                    continue;
+                }
+                children.push((child, child_remaining.clone()));
+            }
+        }
+        // Visit the most promising first, so the good layouts are found before the budget runs out.
+        self.tie_breaker.shuffle(&mut children);
+        children.sort_by_key(|(child, _)| cmp::Reverse(Self::score(child)));
+        for (child, child_remaining) in children {
+            self.visit(child, &child_remaining);
+        }
+    }
+}
+
+/// The valid words without repetitions and without the words already on the layout. The same word twice would
+/// be placed on top of itself.
+fn distinct_words<'a>(layout: &Layout, words: &[&'a str]) -> Vec<&'a str> {
+    let placed = layout.words();
+    let mut distinct: Vec<&str> = Vec::new();
+    for word in words {
+        if !distinct.contains(word) && !placed.contains(word) && validate_word(word).is_ok() {
+            distinct.push(word);
+        }
+    }
+    distinct
+}
+
+/// The words and the pinned words that are not among them, to report on.
+fn with_pinned_words<'a>(words: &[&'a str], pins: &'a [WordPosition]) -> Vec<&'a str> {
+    let mut all_words = words.to_vec();
+    for pin in pins {
+        if !words.contains(&pin.word.as_str()) {
+            all_words.push(&pin.word);
+        }
+    }
+    all_words
+}
+
+/// Order the words in which the greedy modes try them.
+fn sort_for_mode(words: &mut [&str], mode: GeneratorMode) {
+    if let GeneratorMode::Automatic = mode {
+        // Place larger first. Otherwise one will end up with a tiny shape that cannot be extended.
+        words.sort_by_key(|w| cmp::Reverse(w.len()));
+    }
+}
+
+/// The words that are not on the layout, e.g. because they do not cross any other word, or do not fit.
+pub fn unplaced_words<'a>(words: &[&'a str], layout: &Layout) -> Vec<&'a str> {
+    let mut placed = layout.words();
+    let mut unplaced = Vec::new();
+    for word in words {
+        match placed.iter().position(|w| w == word) {
+            Some(i) => {
+                placed.remove(i);
             }
+            None => unplaced.push(*word),
         }
-        cmp::Ordering::Equal
     }
+    unplaced
 }
 
-pub fn generate_crossword(words: &Vec<&str>, mode: GeneratorMode) -> Layout {
-    let comparators: Vec<Box<dyn Fn(&Layout, &Layout) -> cmp::Ordering>>;
-    comparators = match mode {
+impl CrosswordGenerator {
+    /// A generator with the scorer that works best for the given mode.
+    pub fn for_mode(mode: GeneratorMode) -> CrosswordGenerator {
+        CrosswordGenerator::new(default_scorer(mode))
+    }
+
+    /// Like [CrosswordGenerator::for_mode], but prefer the layouts with the given width to height ratio.
+    pub fn for_mode_with_aspect_ratio(mode: GeneratorMode, target: f64) -> CrosswordGenerator {
+        let scorer: Box<dyn Scorer> = match mode {
+            // As with the area, optimizing for the fitted area in automatic mode would end up with a tiny initial
+            // crossword, but the shape alone is fine.
+            GeneratorMode::Automatic | GeneratorMode::Beam => Box::new(Lexicographic(vec![
+                Box::new(Crossings),
+                Box::new(AspectRatio(target)),
+            ])),
+            GeneratorMode::InputOrder | GeneratorMode::Exhaustive => Box::new(Lexicographic(vec![
+                Box::new(Crossings),
+                Box::new(FittedArea(target)),
+            ])),
+        };
+        CrosswordGenerator::new(scorer)
+    }
+}
+
+/// The scorer that works best for the given mode.
+pub fn default_scorer(mode: GeneratorMode) -> Box<dyn Scorer> {
+    match mode {
         // In automatic mode, we don't want to optimize for area because we would end up with tiny initial crossword that would not extend.
-        GeneratorMode::Automatic => vec![Box::new(compare_crossings)],
-        GeneratorMode::InputOrder => vec![Box::new(compare_crossings), Box::new(compare_area)],
+        GeneratorMode::Automatic | GeneratorMode::Beam => Box::new(Crossings),
+        GeneratorMode::InputOrder | GeneratorMode::Exhaustive => {
+            Box::new(Lexicographic(vec![Box::new(Crossings), Box::new(Area)]))
+        }
+    }
+}
+
+pub fn generate_crossword(words: &[&str], mode: GeneratorMode) -> GenerationReport {
+    CrosswordGenerator::for_mode(mode).generate_crossword(words, mode)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::cell::RefCell;
+    use std::cmp;
+    use std::rc::Rc;
+
+    use crate::{Layout, Orientation, Scorer};
+
+    use super::{
+        Area, AspectRatio, CancellationToken, Crossings, CrosswordGenerator, GeneratorMode,
+        LayoutError, Lexicographic, Progress, SearchBudget, SizeLimit, UnplacedReason,
+        WordPosition, generate_crossword, unplaced_words,
     };
-    CrosswordGenerator::new(comparators).generate_crossword(words, mode)
+
+    /// Four words that all fit together, small enough for [GeneratorMode::Exhaustive].
+    pub(crate) const POLISH_WORDS: [&str; 4] = ["burak", "bacz", "kark", "zlepk"];
+    /// Eight words with many ways to cross.
+    pub(crate) const ENGLISH_WORDS: [&str; 8] = [
+        "rabbit", "termite", "mob", "boar", "robot", "bird", "car", "trap",
+    ];
+
+    #[test]
+    fn test_exhaustive_not_worse_than_greedy() {
+        let words = POLISH_WORDS;
+        let greedy = generate_crossword(&words, GeneratorMode::Automatic).layout;
+        let exhaustive = generate_crossword(&words, GeneratorMode::Exhaustive).layout;
+        eprintln!("greedy:\n{greedy}\n\nexhaustive:\n{exhaustive}");
+        assert_eq!(exhaustive.get_word_positions().len(), words.len());
+        assert!(exhaustive.crossings_count() >= greedy.crossings_count());
+        assert_eq!(exhaustive.crossings_count(), 4);
+        assert_eq!(exhaustive.area(), 20);
+    }
+
+    #[test]
+    fn test_exhaustive_uses_scorer() {
+        let words = POLISH_WORDS;
+        let mode = GeneratorMode::Exhaustive;
+        let default = generate_crossword(&words, mode).layout;
+        let scorer = Lexicographic(vec![Box::new(AspectRatio(9.0)), Box::new(Crossings)]);
+        let wide = CrosswordGenerator::new(Box::new(scorer))
+            .generate_crossword(&words, mode)
+            .layout;
+        eprintln!("default:\n{default}\n\nwide:\n{wide}");
+        assert_eq!(wide.get_word_positions().len(), words.len());
+        let scorer = AspectRatio(9.0);
+        assert_eq!(scorer.compare(&wide, &default), cmp::Ordering::Greater);
+    }
+
+    #[test]
+    fn test_exhaustive_respects_budget() {
+        let words = POLISH_WORDS;
+        let budget = SearchBudget {
+            max_nodes: Some(1),
+            max_duration: None,
+        };
+        let generator =
+            CrosswordGenerator::new(Box::new(Lexicographic(vec![]))).with_budget(budget);
+        let layout = generator
+            .generate_crossword(&words, GeneratorMode::Exhaustive)
+            .layout;
+        let greedy = generator
+            .generate_crossword(&words, GeneratorMode::Automatic)
+            .layout;
+        // A single node is not enough to beat the greedy layout.
+        assert_eq!(format!("{layout}"), format!("{greedy}"));
+    }
+
+    #[test]
+    fn test_beam_not_worse_than_greedy() {
+        let words = ENGLISH_WORDS;
+        let greedy = CrosswordGenerator::new(Box::new(Crossings))
+            .generate_crossword(&words, GeneratorMode::Automatic)
+            .layout;
+        let beam = CrosswordGenerator::new(Box::new(Crossings))
+            .with_beam_width(16)
+            .generate_crossword(&words, GeneratorMode::Beam)
+            .layout;
+        eprintln!("greedy:\n{greedy}\n\nbeam:\n{beam}");
+        assert!(beam.get_word_positions().len() >= greedy.get_word_positions().len());
+        assert!(beam.crossings_count() >= greedy.crossings_count());
+    }
+
+    #[test]
+    fn test_beam_width_one_is_greedy() {
+        let words = POLISH_WORDS;
+        let generator = CrosswordGenerator::new(Box::new(Lexicographic(vec![
+            Box::new(Crossings),
+            Box::new(Area),
+        ])))
+        .with_beam_width(1);
+        let beam = generator
+            .generate_crossword(&words, GeneratorMode::Beam)
+            .layout;
+        let greedy = generator
+            .generate_crossword(&words, GeneratorMode::Automatic)
+            .layout;
+        assert_eq!(format!("{beam}"), format!("{greedy}"));
+    }
+
+    #[test]
+    fn test_seed_is_reproducible() {
+        let words = ENGLISH_WORDS;
+        for mode in [
+            GeneratorMode::Automatic,
+            GeneratorMode::InputOrder,
+            GeneratorMode::Beam,
+        ] {
+            let generate = |seed| {
+                let layout = CrosswordGenerator::for_mode(mode)
+                    .with_seed(seed)
+                    .generate_crossword(&words, mode)
+                    .layout;
+                format!("{layout}")
+            };
+            assert_eq!(generate(Some(1)), generate(Some(1)));
+            assert_eq!(generate(None), generate(None));
+        }
+        let layouts: std::collections::HashSet<String> = (0..10)
+            .map(|seed| {
+                let layout = CrosswordGenerator::for_mode(GeneratorMode::Automatic)
+                    .with_seed(Some(seed))
+                    .generate_crossword(&words, GeneratorMode::Automatic)
+                    .layout;
+                format!("{layout}")
+            })
+            .collect();
+        assert!(layouts.len() > 1);
+    }
+
+    #[test]
+    fn test_alternatives_are_distinct() {
+        let words = ENGLISH_WORDS;
+        let generator = CrosswordGenerator::for_mode(GeneratorMode::Automatic);
+        let alternatives = generator
+            .generate_alternatives(&words, &[], GeneratorMode::Automatic, 3, 0.2)
+            .unwrap();
+        assert!(alternatives.len() > 1);
+        assert!(alternatives.len() <= 3);
+        for pair in alternatives.windows(2) {
+            assert!(pair[0].get_word_positions().len() >= pair[1].get_word_positions().len());
+        }
+        for (i, a) in alternatives.iter().enumerate() {
+            for b in &alternatives[i + 1..] {
+                assert!(a.distance(b) >= 0.2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_alternatives_share_budget() {
+        let words = POLISH_WORDS;
+        let budget = SearchBudget {
+            max_nodes: Some(1200),
+            max_duration: None,
+        };
+        assert_eq!(budget.divided(12).max_nodes, Some(100));
+        let generator = CrosswordGenerator::for_mode(GeneratorMode::Exhaustive).with_budget(budget);
+        let alternatives = generator
+            .generate_alternatives(&words, &[], GeneratorMode::Exhaustive, 3, 0.0)
+            .unwrap();
+        assert!(!alternatives.is_empty());
+        assert_eq!(generator.budget.get().max_nodes, Some(1200));
+    }
+
+    #[test]
+    fn test_insert_word_keeps_placed_words() {
+        let generator = CrosswordGenerator::for_mode(GeneratorMode::InputOrder);
+        let layout = generate_crossword(&["burak", "bacz"], GeneratorMode::InputOrder).layout;
+        let extended = generator.insert_word(&layout, "zlepk").unwrap();
+        let before: Vec<String> = format!("{layout}").lines().map(|l| l.to_owned()).collect();
+        let after = format!("{extended}");
+        // The old rows are still in place, only extended.
+        for (old, new) in before.iter().zip(after.lines()) {
+            assert!(new.starts_with(old.trim_end()));
+        }
+        assert_eq!(extended.words(), vec!["burak", "bacz", "zlepk"]);
+
+        assert!(generator.insert_word(&extended, "xyz").is_none());
+    }
+
+    #[test]
+    fn test_extend_crossword_regenerates_if_word_does_not_fit() {
+        let generator = CrosswordGenerator::for_mode(GeneratorMode::InputOrder);
+        let layout = generate_crossword(&["ab"], GeneratorMode::InputOrder).layout;
+        let (layout, is_incremental) = generator
+            .extend_crossword(&layout, "bc", &[], GeneratorMode::InputOrder)
+            .unwrap();
+        assert!(is_incremental);
+        let (layout, is_incremental) = generator
+            .extend_crossword(&layout, "xyz", &[], GeneratorMode::InputOrder)
+            .unwrap();
+        assert!(!is_incremental);
+        assert_eq!(layout.words(), vec!["ab", "bc"]);
+    }
+
+    #[test]
+    fn test_extend_crossword_skips_placed_word() {
+        let generator = CrosswordGenerator::for_mode(GeneratorMode::InputOrder);
+        let layout = generate_crossword(&["burak", "kark"], GeneratorMode::InputOrder).layout;
+        assert!(generator.insert_word(&layout, "burak").is_none());
+        let (extended, is_incremental) = generator
+            .extend_crossword(&layout, "burak", &[], GeneratorMode::InputOrder)
+            .unwrap();
+        assert!(is_incremental);
+        assert_eq!(extended.words(), vec!["burak", "kark"]);
+        assert_eq!(extended.crossings_count(), layout.crossings_count());
+    }
+
+    #[test]
+    fn test_pinned_words_stay_in_place() {
+        let pins = vec![
+            WordPosition {
+                word: "zlepk".to_owned(),
+                pos: (5, 5).into(),
+                orientation: Orientation::Vert,
+            },
+            WordPosition {
+                word: "burak".to_owned(),
+                pos: (1, 9).into(),
+                orientation: Orientation::Horiz,
+            },
+        ];
+        let find = |layout: &Layout, word: &str| {
+            let words = layout.get_word_positions();
+            words.iter().find(|wp| wp.word == word).unwrap().clone()
+        };
+        let words = POLISH_WORDS;
+        for mode in [
+            GeneratorMode::Automatic,
+            GeneratorMode::InputOrder,
+            GeneratorMode::Beam,
+            GeneratorMode::Exhaustive,
+        ] {
+            let layout = CrosswordGenerator::for_mode(mode)
+                .generate_pinned_crossword(&words, &pins, mode)
+                .unwrap()
+                .layout;
+            let (zlepk, burak) = (find(&layout, "zlepk"), find(&layout, "burak"));
+            assert_eq!(zlepk.orientation, Orientation::Vert);
+            assert_eq!(burak.orientation, Orientation::Horiz);
+            // Shifted together with the layout, so that it starts at 0, 0.
+            assert_eq!(zlepk.pos - burak.pos, (4, -4).into());
+            assert_eq!(layout.get_word_positions().len(), words.len());
+        }
+
+        // The pins stay when adding a word generates the layout again, and in the alternatives.
+        let mode = GeneratorMode::Automatic;
+        let generator = CrosswordGenerator::for_mode(mode);
+        let layout = generator
+            .generate_pinned_crossword(&words, &pins, mode)
+            .unwrap()
+            .layout;
+        let (extended, is_incremental) = generator
+            .extend_crossword(&layout, "fox", &pins, mode)
+            .unwrap();
+        assert!(!is_incremental);
+        let alternatives = generator
+            .generate_alternatives(&words, &pins, mode, 3, 0.0)
+            .unwrap();
+        for layout in alternatives.iter().chain([&extended]) {
+            assert!(
+                layout
+                    .get_word_positions()
+                    .iter()
+                    .any(|wp| wp.word == "zlepk" && wp.orientation == Orientation::Vert)
+            );
+        }
+    }
+
+    #[test]
+    fn test_pinned_word_in_words_is_not_duplicate() {
+        let pins = vec![WordPosition {
+            word: "abc".to_owned(),
+            pos: (0, 0).into(),
+            orientation: Orientation::Horiz,
+        }];
+        let mode = GeneratorMode::InputOrder;
+        let generator = CrosswordGenerator::for_mode(mode);
+        let report = generator
+            .generate_pinned_crossword(&["abc", "cde"], &pins, mode)
+            .unwrap();
+        assert_eq!(report.layout.words(), vec!["abc", "cde"]);
+        assert!(report.unplaced.is_empty());
+        let mut steps = generator
+            .into_pinned_steps(&["abc", "cde", "abc"], &pins, mode)
+            .unwrap();
+        steps.by_ref().for_each(drop);
+        let unplaced = steps.report().unplaced;
+        assert_eq!(unplaced.len(), 1);
+        assert_eq!(unplaced[0].reason, UnplacedReason::Duplicate);
+    }
+
+    #[test]
+    fn test_conflicting_pins() {
+        let pin = |word: &str, x: i32, y: i32, orientation| WordPosition {
+            word: word.to_owned(),
+            pos: (x, y).into(),
+            orientation,
+        };
+        let generator = CrosswordGenerator::for_mode(GeneratorMode::InputOrder);
+        let pins = vec![
+            pin("abc", 0, 0, Orientation::Horiz),
+            pin("xyz", 2, 0, Orientation::Vert),
+        ];
+        let err = generator
+            .generate_pinned_crossword(&[], &pins, GeneratorMode::InputOrder)
+            .unwrap_err();
+        assert_eq!(err.pin.word, "xyz");
+        assert_eq!(
+            err.error,
+            LayoutError::ConflictingLetters {
+                pos: (2, 0).into(),
+                existing: 'c',
+                new: 'x',
+            }
+        );
+
+        let pins = vec![
+            pin("abc", 0, 0, Orientation::Horiz),
+            pin("abc", 0, 0, Orientation::Horiz),
+        ];
+        let err = generator
+            .generate_pinned_crossword(&[], &pins, GeneratorMode::InputOrder)
+            .unwrap_err();
+        assert_eq!(
+            err.error,
+            LayoutError::EnvelopeOverlap { pos: (0, 0).into() }
+        );
+
+        let pins = vec![
+            pin("abc", 0, 0, Orientation::Horiz),
+            pin("xyz", 0, 1, Orientation::Horiz),
+        ];
+        let err = generator
+            .generate_pinned_crossword(&[], &pins, GeneratorMode::InputOrder)
+            .unwrap_err();
+        assert!(matches!(err.error, LayoutError::EnvelopeOverlap { .. }));
+    }
+
+    #[test]
+    fn test_report() {
+        let mut layout = Layout::new();
+        layout.insert_at("xab", (0, 0), Orientation::Horiz).unwrap();
+        layout.insert_at("xyz", (0, 0), Orientation::Vert).unwrap();
+        let generator =
+            CrosswordGenerator::for_mode(GeneratorMode::InputOrder).with_size_limit(SizeLimit {
+                max_width: Some(3),
+                max_height: Some(3),
+            });
+        let words = ["xab", "xyz", "qxq", "pp", "xab", "bcde", "ba"];
+        let report = generator.report(layout, &words);
+        let reasons: Vec<(&str, UnplacedReason)> = report
+            .unplaced
+            .iter()
+            .map(|u| (u.word.as_str(), u.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("qxq", UnplacedReason::AllPositionsConflict),
+                ("pp", UnplacedReason::NoSharedLetters),
+                ("xab", UnplacedReason::Duplicate),
+                ("bcde", UnplacedReason::BlockedByConstraint),
+                ("ba", UnplacedReason::NotReached),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicates_are_placed_once() {
+        let report = generate_crossword(&["burak", "kark", "burak"], GeneratorMode::InputOrder);
+        assert_eq!(report.layout.words(), vec!["burak", "kark"]);
+        assert_eq!(report.unplaced.len(), 1);
+        assert_eq!(report.unplaced[0].reason, UnplacedReason::Duplicate);
+    }
+
+    #[test]
+    fn test_invalid_words_are_reported() {
+        let words = ["ice cream", "cat", "ice", "", "ice cream"];
+        for mode in [
+            GeneratorMode::Automatic,
+            GeneratorMode::InputOrder,
+            GeneratorMode::Beam,
+            GeneratorMode::Exhaustive,
+        ] {
+            let report = generate_crossword(&words, mode);
+            assert_eq!(report.layout.get_word_positions().len(), 2);
+            let reasons: Vec<UnplacedReason> = report.unplaced.iter().map(|u| u.reason).collect();
+            assert_eq!(
+                reasons,
+                vec![
+                    UnplacedReason::InvalidWord,
+                    UnplacedReason::EmptyWord,
+                    UnplacedReason::InvalidWord
+                ]
+            );
+            assert_eq!(report.unplaced[1].reason.to_string(), "empty word");
+        }
+        let generator = CrosswordGenerator::for_mode(GeneratorMode::Automatic);
+        let layout = generate_crossword(&["cat"], GeneratorMode::Automatic).layout;
+        assert!(generator.insert_word(&layout, "ice cream").is_none());
+    }
+
+    #[test]
+    fn test_steps() {
+        let words = ENGLISH_WORDS;
+        for mode in [
+            GeneratorMode::Automatic,
+            GeneratorMode::InputOrder,
+            GeneratorMode::Beam,
+        ] {
+            let expected = CrosswordGenerator::for_mode(mode).generate_crossword(&words, mode);
+            let mut steps = CrosswordGenerator::for_mode(mode).into_steps(&words, mode);
+            let mut n_steps = 0;
+            for layout in steps.by_ref() {
+                n_steps += 1;
+                assert_eq!(layout.get_word_positions().len(), n_steps);
+            }
+            let report = steps.report();
+            assert_eq!(n_steps, expected.layout.get_word_positions().len());
+            assert_eq!(format!("{}", report.layout), format!("{}", expected.layout));
+            assert_eq!(report.unplaced, expected.unplaced);
+        }
+    }
+
+    #[test]
+    fn test_cancel() {
+        let words = ENGLISH_WORDS;
+        for mode in [
+            GeneratorMode::Automatic,
+            GeneratorMode::Beam,
+            GeneratorMode::Exhaustive,
+        ] {
+            let token = CancellationToken::new();
+            let reports = Rc::new(RefCell::new(Vec::new()));
+            let on_progress = {
+                let token = token.clone();
+                let reports = reports.clone();
+                move |progress: &Progress| {
+                    reports.borrow_mut().push(*progress);
+                    token.cancel();
+                }
+            };
+            let report = CrosswordGenerator::for_mode(mode)
+                .with_cancellation(token)
+                .with_progress(Box::new(on_progress))
+                .generate_crossword(&words, mode);
+            assert!(report.cancelled);
+            // The best layout so far is returned.
+            assert_eq!(report.layout.get_word_positions().len(), 1);
+            assert_eq!(report.unplaced.len(), words.len() - 1);
+            let reports = reports.borrow();
+            assert_eq!(reports[0].words_placed, 1);
+            assert!(reports[0].candidates_evaluated > 0);
+        }
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let words = ENGLISH_WORDS;
+        let size_limit = SizeLimit {
+            max_width: Some(7),
+            max_height: Some(5),
+        };
+        for mode in [
+            GeneratorMode::Automatic,
+            GeneratorMode::InputOrder,
+            GeneratorMode::Beam,
+            GeneratorMode::Exhaustive,
+        ] {
+            let layout = CrosswordGenerator::for_mode(mode)
+                .with_size_limit(size_limit)
+                .with_budget(SearchBudget {
+                    max_nodes: Some(1000),
+                    max_duration: None,
+                })
+                .generate_crossword(&words, mode)
+                .layout;
+            eprintln!("{mode:?}:\n{layout}");
+            assert!(size_limit.allows(layout.dimensions()));
+            assert_eq!(
+                layout.get_word_positions().len() + unplaced_words(&words, &layout).len(),
+                words.len()
+            );
+        }
+    }
 }
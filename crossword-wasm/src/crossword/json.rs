@@ -0,0 +1,152 @@
+//! The JSON format of a [Puzzle], to save puzzles to files, pass them between the command line and the app,
+//! and keep them as test fixtures. Available with the "serde" feature.
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "metadata": { "title": "Animals", "language": "pl" },
+//!   "layout": [
+//!     { "word": "burak", "pos": { "x": 0, "y": 0 }, "orientation": "hor" },
+//!     { "word": "kark", "pos": { "x": 4, "y": 0 }, "orientation": "ver" }
+//!   ],
+//!   "clues": { "burak": "A red root vegetable" }
+//! }
+//! ```
+//!
+//! The format is stable: a reader of version N reads all the files of version N and older. A change that older
+//! readers would get wrong gets a new version. Version 1 had only the layout.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::Puzzle;
+
+/// The version written by [puzzle_to_json].
+pub const PUZZLE_FORMAT_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct PuzzleOut<'a> {
+    version: u32,
+    #[serde(flatten)]
+    puzzle: &'a Puzzle,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum JsonError {
+    /// Not a puzzle, e.g. a field is missing or a word has invalid characters.
+    Json(serde_json::Error),
+    /// A version newer than [PUZZLE_FORMAT_VERSION].
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Json(err) => write!(f, "bad puzzle: {err}"),
+            JsonError::UnsupportedVersion(version) => {
+                write!(f, "unsupported puzzle version: {version}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(err: serde_json::Error) -> Self {
+        JsonError::Json(err)
+    }
+}
+
+pub fn puzzle_to_json(puzzle: &Puzzle) -> String {
+    let puzzle = PuzzleOut {
+        version: PUZZLE_FORMAT_VERSION,
+        puzzle,
+    };
+    // The puzzle has only strings and numbers, so it always serializes.
+    serde_json::to_string_pretty(&puzzle).unwrap()
+}
+
+/// Read the puzzle written by [puzzle_to_json]. The words stay where they were, they are not normalized.
+pub fn puzzle_from_json(json: &str) -> Result<Puzzle, JsonError> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let Version { version } = Version::deserialize(&value)?;
+    if version > PUZZLE_FORMAT_VERSION {
+        return Err(JsonError::UnsupportedVersion(version));
+    }
+    Ok(Puzzle::deserialize(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonError, puzzle_from_json, puzzle_to_json};
+    use crate::crossword::generator::tests::POLISH_WORDS;
+    use crate::{CrosswordGenerator, GeneratorMode, Puzzle, Trace};
+
+    #[test]
+    fn test_round_trip() {
+        let words = POLISH_WORDS;
+        let report = CrosswordGenerator::for_mode(GeneratorMode::Automatic)
+            .with_trace(true)
+            .generate_crossword(&words, GeneratorMode::Automatic);
+        let mut puzzle = Puzzle::new(report.layout.clone());
+        puzzle.set_clue("burak", "A red root vegetable");
+        puzzle.metadata.title = Some("Animals".to_owned());
+        puzzle.metadata.notes = Some("first\nsecond".to_owned());
+        let json = puzzle_to_json(&puzzle);
+        let read = puzzle_from_json(&json).unwrap();
+        let layout = &read.layout;
+        assert_eq!(
+            layout.get_word_positions(),
+            report.layout.get_word_positions()
+        );
+        assert_eq!(format!("{layout}"), format!("{}", report.layout));
+        assert_eq!(layout.crossings_count(), report.layout.crossings_count());
+        assert_eq!(read.clues, puzzle.clues);
+        assert_eq!(read.metadata, puzzle.metadata);
+
+        let trace = report.trace.unwrap();
+        let parsed: Trace = serde_json::from_str(&serde_json::to_string(&trace).unwrap()).unwrap();
+        assert_eq!(parsed, trace);
+    }
+
+    #[test]
+    fn test_load() {
+        let json = r#"{
+            "version": 1,
+            "layout": [
+                { "word": "burak", "pos": { "x": 0, "y": 0 }, "orientation": "hor" },
+                { "word": "kark", "pos": { "x": 4, "y": 0 }, "orientation": "ver" }
+            ]
+        }"#;
+        let puzzle = puzzle_from_json(json).unwrap();
+        assert_eq!(puzzle.layout.char_at((4, 3)), Some('k'));
+        assert_eq!(puzzle.layout.crossings_count(), 1);
+        assert!(puzzle.clues.is_empty());
+
+        let conflicting = puzzle_from_json(&json.replace(r#""x": 4"#, r#""x": 3"#)).unwrap();
+        assert_eq!(conflicting.layout.words(), vec!["burak", "kark"]);
+        assert!(!conflicting.layout.validate().is_empty());
+        let reread = puzzle_from_json(&puzzle_to_json(&conflicting)).unwrap();
+        assert_eq!(
+            reread.layout.get_word_positions(),
+            conflicting.layout.get_word_positions()
+        );
+        let invalid = json.replace("kark", "ka rk");
+        assert!(matches!(
+            puzzle_from_json(&invalid),
+            Err(JsonError::Json(_))
+        ));
+        let newer = json.replace(r#""version": 1"#, r#""version": 3"#);
+        assert!(matches!(
+            puzzle_from_json(&newer),
+            Err(JsonError::UnsupportedVersion(3))
+        ));
+    }
+}
@@ -1,9 +1,23 @@
 use std::cmp::{max, min};
 use std::hash::Hash;
-use std::{collections::HashMap, fmt, ops};
+use std::str::FromStr;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, ops,
+};
+
+use wasm_bindgen::prelude::*;
 
 /// Layout of the words: position and orientation per word.
-#[derive(Clone)]
+///
+/// With the "serde" feature the layout is (de)serialized as the list of its words. Loading rebuilds the
+/// letters, and keeps conflicting words for [Layout::validate] to report, see [Layout::try_from].
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "Vec<WordPosition>", try_from = "Vec<WordPosition>")
+)]
 pub struct Layout {
     /// The words with positions.
     positioned_words: Vec<WordPosition>,
@@ -14,7 +28,37 @@ pub struct Layout {
     n_crossings: usize,
 }
 
-type LayoutResult<T = ()> = Result<T, ()>;
+pub type LayoutResult<T = ()> = Result<T, LayoutError>;
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Layout> for Vec<WordPosition> {
+    fn from(layout: Layout) -> Self {
+        layout.positioned_words
+    }
+}
+
+impl TryFrom<Vec<WordPosition>> for Layout {
+    type Error = LayoutError;
+
+    /// Put the words on a layout at their positions, in order. Fail on the first word that cannot be put on
+    /// a layout. A word with a letter conflicting with the earlier words is kept, like
+    /// [Layout::insert_at] does, and [Layout::validate] reports it.
+    fn try_from(words: Vec<WordPosition>) -> Result<Self, Self::Error> {
+        let mut layout = Layout::new();
+        for wp in words {
+            match layout.insert_at(&wp.word, wp.pos, wp.orientation) {
+                Ok(()) | Err(LayoutError::ConflictingLetters { .. }) => (),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(layout)
+    }
+}
 
 impl Layout {
     pub fn new() -> Layout {
@@ -29,6 +73,24 @@ impl Layout {
         &self.positioned_words
     }
 
+    /// The letter at the position, if any.
+    pub fn char_at<A: Into<XY>>(&self, pos: A) -> Option<char> {
+        self.char_map.pos_to_char.get(&pos.into()).copied()
+    }
+
+    /// Tell if the letter is anywhere on the layout.
+    pub fn has_char(&self, c: char) -> bool {
+        self.char_map.char_to_pos.contains_key(&c)
+    }
+
+    /// The placed words, in the order of insertion.
+    pub fn words(&self) -> Vec<&str> {
+        self.positioned_words
+            .iter()
+            .map(|wp| wp.word.as_str())
+            .collect()
+    }
+
     /// Assign a number per word. The (id, orientation) is unique, there can be two words starting at the same
     /// field of the grid with different orientation. Theoretically there can be two words totally overlapping
     /// with the same orientation and id, like "mass" and "massage".
@@ -55,24 +117,160 @@ impl Layout {
     }
 
     pub fn area(&self) -> u32 {
+        let dim = self.dimensions();
+        (dim.x * dim.y) as u32
+    }
+
+    /// Share of the fields of the enclosing rectangle that have a letter, from 0.0 to 1.0. The higher, the more
+    /// compact the layout is. An empty layout has density 0.0.
+    pub fn density(&self) -> f64 {
+        let area = self.area();
+        if area == 0 {
+            return 0.0;
+        }
+        self.char_map.pos_to_char.len() as f64 / area as f64
+    }
+
+    /// Width and height of the rectangle enclosing all the letters.
+    pub fn dimensions(&self) -> XY {
         if self.char_map.top_left.is_none() && self.char_map.bottom_right.is_none() {
-            return 0;
+            return XY::zero();
         }
         let top_left = self.char_map.top_left.unwrap();
         let bottom_right = self.char_map.bottom_right.unwrap();
         let dim = bottom_right - top_left + XY::one();
         assert!(dim.x > 0);
         assert!(dim.y > 0);
-        return (dim.x * dim.y) as u32;
+        dim
+    }
+
+    /// Width and height the layout would have with the word put at the position.
+    pub fn dimensions_with(&self, word: &str, pos: XY, orient: Orientation) -> XY {
+        let word_end = pos + orient.step() * (word.chars().count() as i32 - 1);
+        let (top_left, bottom_right) = match (self.char_map.top_left, self.char_map.bottom_right) {
+            (Some(top_left), Some(bottom_right)) => (top_left, bottom_right),
+            _ => (pos, word_end),
+        };
+        let top_left = XY {
+            x: min(top_left.x, pos.x),
+            y: min(top_left.y, pos.y),
+        };
+        let bottom_right = XY {
+            x: max(bottom_right.x, word_end.x),
+            y: max(bottom_right.y, word_end.y),
+        };
+        bottom_right - top_left + XY::one()
     }
 
     pub fn crossings_count(&self) -> usize {
         self.n_crossings
     }
 
+    /// Tell if all the words form a single crossword, i.e. one can get from each word to any other word by
+    /// following the crossings.
+    pub fn is_connected(&self) -> bool {
+        self.connected_words().len() == self.positioned_words.len()
+    }
+
+    /// The indices of the words that can be reached from the first word by following the crossings.
+    fn connected_words(&self) -> HashSet<usize> {
+        let mut pos_to_words: HashMap<XY, Vec<usize>> = HashMap::new();
+        for (i, wp) in self.positioned_words.iter().enumerate() {
+            for pos in wp.cells() {
+                pos_to_words.entry(pos).or_default().push(i);
+            }
+        }
+        let mut visited: HashSet<usize> = HashSet::new();
+        let mut to_visit: Vec<usize> = vec![0];
+        while let Some(i) = to_visit.pop() {
+            if i >= self.positioned_words.len() || !visited.insert(i) {
+                continue;
+            }
+            for pos in self.positioned_words[i].cells() {
+                to_visit.extend(&pos_to_words[&pos]);
+            }
+        }
+        visited
+    }
+
+    /// Find the words that break the rules the generator keeps: the words that conflict with the words before
+    /// them, and the words that are not connected to the first word. Such layouts come from placing the words
+    /// by hand, or from removing a word that connected the others.
+    ///
+    /// # Returns
+    /// The index of the word in [Layout::get_word_positions] and what is wrong with it. Empty if the layout
+    /// is fine.
+    pub fn validate(&self) -> Vec<(usize, LayoutError)> {
+        let mut issues = Vec::new();
+        let mut layout = Layout::new();
+        for (i, wp) in self.positioned_words.iter().enumerate() {
+            if let Some(error) = layout.find_placement_conflict(&wp.word, wp.pos, wp.orientation) {
+                issues.push((i, error));
+            }
+            let _ = layout.insert_at(&wp.word, wp.pos, wp.orientation);
+        }
+        let connected = self.connected_words();
+        for i in 0..self.positioned_words.len() {
+            if !connected.contains(&i) {
+                issues.push((i, LayoutError::Disconnected));
+            }
+        }
+        issues
+    }
+
+    /// How different are two layouts, from 0.0 for the same grid to 1.0 for grids with no letter in common. Layouts
+    /// that differ only by translation or by transposition are the same. The distance is the share of the letters
+    /// that are on only one of the grids.
+    pub fn distance(&self, other: &Layout) -> f64 {
+        let cells = self.normalized_cells();
+        let other_cells = other.normalized_cells();
+        let transposed_cells: HashSet<(XY, char)> = other_cells
+            .iter()
+            .map(|(pos, c)| (XY { x: pos.y, y: pos.x }, *c))
+            .collect();
+        let jaccard_distance = |a: &HashSet<(XY, char)>, b: &HashSet<(XY, char)>| {
+            let union = a.union(b).count();
+            if union == 0 {
+                return 0.0;
+            }
+            1.0 - a.intersection(b).count() as f64 / union as f64
+        };
+        f64::min(
+            jaccard_distance(&cells, &other_cells),
+            jaccard_distance(&cells, &transposed_cells),
+        )
+    }
+
+    fn normalized_cells(&self) -> HashSet<(XY, char)> {
+        self.char_map.normalize().pos_to_char.into_iter().collect()
+    }
+
+    /// Remove the word at the index of [Layout::get_word_positions]. The letters shared with other words stay.
+    ///
+    /// # Panics
+    /// If the index is out of bounds.
+    pub fn remove_word(&mut self, index: usize) -> WordPosition {
+        let wp = self.positioned_words.remove(index);
+        if self.char_map.has_conflict {
+            // With conflicts the map does not reflect the words exactly, so build it again from the words.
+            self.char_map = CharMap::new();
+            self.n_crossings = 0;
+            for wp in &self.positioned_words {
+                if let Ok(n_crossings) = self.char_map.insert_word(&wp.word, wp.pos, wp.orientation)
+                {
+                    self.n_crossings += n_crossings;
+                }
+            }
+        } else {
+            self.n_crossings -= self.char_map.remove_word(&wp.word, wp.pos, wp.orientation);
+        }
+        wp
+    }
+
     /// # Returns
-    /// If the returned value is Err it means that there was a conflict on insertion. If Ok, the
-    /// number in Ok tells how many other words did this word cut.
+    /// [LayoutError::EmptyWord] or [LayoutError::InvalidChars] if the word cannot be put on any layout, then
+    /// the layout is not changed. [LayoutError::ConflictingLetters] if there was a conflict on insertion, then
+    /// the word is inserted anyway and the conflicting field is marked on the layout.
     pub fn insert_at<A: Into<XY>>(
         &mut self,
         word: &str,
@@ -80,6 +278,7 @@ impl Layout {
         orientation: Orientation,
     ) -> LayoutResult {
         let pos: XY = pos.into();
+        validate_word(word)?;
         self.positioned_words.push(WordPosition {
             word: word.to_owned(),
             pos,
@@ -94,7 +293,7 @@ impl Layout {
                 self.n_crossings += n_crossings;
                 Ok(())
             }
-            Err(_) => Err(()),
+            Err(err) => Err(err),
         }
     }
 
@@ -104,10 +303,8 @@ impl Layout {
     /// # Known bugs
     /// - two same words like "bob" and "bob" will overlap instead of forming two crossing bobs.
     pub fn get_possible_start_positions(&self, word: &str) -> Vec<(XY, Orientation)> {
-        eprintln!("get_possible_start_positions for {word}");
-        // eprintln!("{:?}", &self.positioned_words);
         if self.positioned_words.is_empty() {
-            eprintln!("first word at (0,0) is {word}");
+            log::trace!(word; "first word goes at (0,0)");
             return vec![
                 (XY::zero(), Orientation::Horiz),
                 (XY::zero(), Orientation::Vert),
@@ -125,16 +322,36 @@ impl Layout {
                 if let Some(pos_on_layout_vec) = self.char_map.char_to_pos.get(&curr_char) {
                     for pos_on_layout in pos_on_layout_vec {
                         let word_pos = start_delta + *pos_on_layout;
-                        eprintln!("Consider potential possible start position {word_pos}");
-                        if self.would_conflict_with_other_char(word, &word_pos, orient) {
-                            eprintln!("Position {word_pos} would conflict other char");
+                        if self
+                            .find_conflicting_letter(word, &word_pos, orient)
+                            .is_some()
+                        {
+                            log::trace!(
+                                word,
+                                pos:% = word_pos,
+                                orientation:% = orient;
+                                "position conflicts with other letter"
+                            );
                             continue;
                         }
-                        if self.would_envelope_overlap(word.chars().count(), &word_pos, orient) {
-                            eprintln!("Position {word_pos} would make envelope overlap");
+                        if self
+                            .find_envelope_overlap(word.chars().count(), &word_pos, orient)
+                            .is_some()
+                        {
+                            log::trace!(
+                                word,
+                                pos:% = word_pos,
+                                orientation:% = orient;
+                                "position touches other word"
+                            );
                             continue;
                         }
-                        eprintln!("Add potential position for {word}: {word_pos} {orient}");
+                        log::trace!(
+                            word,
+                            pos:% = word_pos,
+                            orientation:% = orient;
+                            "possible position"
+                        );
                         word_positions.push((word_pos, orient));
                     }
                 };
@@ -143,22 +360,80 @@ impl Layout {
         word_positions
     }
 
+    /// Like [Layout::get_possible_start_positions], but only the positions that keep the layout within the
+    /// size limit.
+    pub fn get_possible_start_positions_within(
+        &self,
+        word: &str,
+        limit: &SizeLimit,
+    ) -> Vec<(XY, Orientation)> {
+        let mut positions = self.get_possible_start_positions(word);
+        if !limit.is_unlimited() {
+            positions
+                .retain(|(pos, orient)| limit.allows(self.dimensions_with(word, *pos, *orient)));
+        }
+        positions
+    }
+
+    /// Tell why the word cannot be put at the position, or None if it can. Unlike
+    /// [Layout::get_possible_start_positions], the word does not have to cross any other word.
+    pub fn find_placement_conflict(
+        &self,
+        word: &str,
+        pos: XY,
+        orient: Orientation,
+    ) -> Option<LayoutError> {
+        if let Err(err) = validate_word(word) {
+            Some(err)
+        } else if let Some(err) = self.find_conflicting_letter(word, &pos, orient) {
+            Some(err)
+        } else {
+            self.find_collinear_overlap(word, pos, orient)
+                .or_else(|| self.find_envelope_overlap(word.chars().count(), &pos, orient))
+                .map(|pos| LayoutError::EnvelopeOverlap { pos })
+        }
+    }
+
+    /// Return the first field that the word would share with other word of the same orientation, e.g. with
+    /// the same word at the same position.
+    fn find_collinear_overlap(&self, word: &str, pos: XY, orient: Orientation) -> Option<XY> {
+        let new = WordPosition {
+            word: word.to_owned(),
+            pos,
+            orientation: orient,
+        };
+        new.cells().find(|cell| {
+            self.positioned_words
+                .iter()
+                .any(|wp| wp.orientation == orient && wp.cells().any(|c| c == *cell))
+        })
+    }
+
     /// Tell if a word put at specific position would result in letters conflicting, i.e. would
-    /// different letters land on the same field of the layout.
-    fn would_conflict_with_other_char(&self, word: &str, pos: &XY, orient: Orientation) -> bool {
+    /// different letters land on the same field of the layout. Return the first such field.
+    fn find_conflicting_letter(
+        &self,
+        word: &str,
+        pos: &XY,
+        orient: Orientation,
+    ) -> Option<LayoutError> {
         let mut pos = *pos;
         for c in word.chars() {
-            if let Some(char_on_layout) = self.char_map.pos_to_char.get(&pos) {
-                if *char_on_layout != c {
-                    return true;
-                }
+            if let Some(char_on_layout) = self.char_map.pos_to_char.get(&pos)
+                && *char_on_layout != c
+            {
+                return Some(LayoutError::ConflictingLetters {
+                    pos,
+                    existing: *char_on_layout,
+                    new: c,
+                });
             }
             pos = pos + orient.step();
         }
-        return false;
+        None
     }
 
-    /// Check if the word would touch other existing word.
+    /// Check if the word would touch other existing word. Return the taken field it would touch.
     ///
     /// For word brown:
     ///
@@ -169,12 +444,12 @@ impl Layout {
     ///   ^x-1
     ///
     /// ```
-    fn would_envelope_overlap(
+    fn find_envelope_overlap(
         &self,
         word_len: usize,
         tentative_start_pos: &XY,
         orient: Orientation,
-    ) -> bool {
+    ) -> Option<XY> {
         let n = word_len as i32;
         let tentative_start_pos = *tentative_start_pos;
 
@@ -182,9 +457,8 @@ impl Layout {
         let tips = [-1, n];
         for i in tips {
             let p = (orient.step() * i) + tentative_start_pos;
-            // eprintln!("would_envelope_overlap({tentative_start_pos}) i={i} p={p}");
             if self.char_map.is_pos_taken(&p) {
-                return true;
+                return Some(p);
             }
         }
 
@@ -197,11 +471,11 @@ impl Layout {
             for b in orient.band() {
                 let p = pos_in_word + b;
                 if self.char_map.is_pos_taken(&p) {
-                    return true;
+                    return Some(p);
                 }
             }
         }
-        return false;
+        None
     }
 
     pub fn normalize(self) -> Layout {
@@ -237,14 +511,109 @@ impl Layout {
     }
 }
 
-#[derive(Clone, Debug)]
+/// Limits the width and the height of a layout. None means no limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SizeLimit {
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+}
+
+impl SizeLimit {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_width.is_none() && self.max_height.is_none()
+    }
+
+    /// Tell if a layout of the dimensions fits within the limit.
+    pub fn allows(&self, dimensions: XY) -> bool {
+        let fits = |dim: i32, limit: Option<u32>| limit.is_none_or(|limit| dim as u32 <= limit);
+        fits(dimensions.x, self.max_width) && fits(dimensions.y, self.max_height)
+    }
+}
+
+/// Why a word cannot be put on a layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// A different letter is already on the field.
+    ConflictingLetters {
+        pos: XY,
+        existing: char,
+        new: char,
+    },
+    /// The word would touch other word side by side, or continue other word. The position is the field of the
+    /// other word that it would touch.
+    EnvelopeOverlap {
+        pos: XY,
+    },
+    /// The layout would not fit within the [SizeLimit].
+    ExceedsSizeLimit,
+    /// The word does not cross the other words, see [Layout::validate].
+    Disconnected,
+    EmptyWord,
+    /// The word has characters that cannot be put on a field, like spaces.
+    InvalidChars {
+        word: String,
+        chars: Vec<char>,
+    },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::ConflictingLetters { pos, existing, new } => {
+                write!(f, "{new:?} would replace {existing:?} at {pos}")
+            }
+            LayoutError::EnvelopeOverlap { pos } => {
+                write!(f, "it would touch other word at {pos}")
+            }
+            LayoutError::ExceedsSizeLimit => write!(f, "the layout would be too large"),
+            LayoutError::Disconnected => write!(f, "it does not cross the other words"),
+            LayoutError::EmptyWord => write!(f, "the word is empty"),
+            LayoutError::InvalidChars { word, chars } => {
+                write!(f, "{word:?} has invalid characters {chars:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// Check that the word can be put on a layout at all.
+pub(crate) fn validate_word(word: &str) -> LayoutResult {
+    if word.is_empty() {
+        return Err(LayoutError::EmptyWord);
+    }
+    let mut chars: Vec<char> = word
+        .chars()
+        .filter(|c| c.is_whitespace() || c.is_control() || *c == CONFLICT_CHAR)
+        .collect();
+    if !chars.is_empty() {
+        chars.dedup();
+        return Err(LayoutError::InvalidChars {
+            word: word.to_owned(),
+            chars,
+        });
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WordPosition {
     pub word: String,
     pub pos: XY,
     pub orientation: Orientation,
 }
 
+impl WordPosition {
+    /// Positions of the consecutive letters of the word.
+    pub fn cells(&self) -> impl Iterator<Item = XY> + '_ {
+        let step = self.orientation.step();
+        (0..self.word.chars().count() as i32).map(move |i| self.pos + step * i)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct XY {
     pub x: i32,
     pub y: i32,
@@ -300,18 +669,23 @@ impl ops::Mul<i32> for XY {
 
 impl<T: Into<i32>> From<(T, T)> for XY {
     fn from(value: (T, T)) -> Self {
-        return XY {
+        XY {
             x: value.0.into(),
             y: value.1.into(),
-        };
+        }
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+/// Serialized as "hor" or "ver", like the pinned words in the command line input.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Orientation {
     /// Left-to-right
+    #[cfg_attr(feature = "serde", serde(rename = "hor"))]
     Horiz,
     /// Top-to-bottom
+    #[cfg_attr(feature = "serde", serde(rename = "ver"))]
     Vert,
 }
 
@@ -324,6 +698,21 @@ impl fmt::Display for Orientation {
     }
 }
 
+impl FromStr for Orientation {
+    type Err = String;
+
+    /// Parse "hor" or "ver", the same as used in the wasm API.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("hor") {
+            Ok(Orientation::Horiz)
+        } else if s.eq_ignore_ascii_case("ver") {
+            Ok(Orientation::Vert)
+        } else {
+            Err(format!("bad orientation: {s}"))
+        }
+    }
+}
+
 impl Orientation {
     fn step(&self) -> XY {
         match self {
@@ -367,7 +756,7 @@ impl fmt::Display for Layout {
             }
         }
         for row in rows {
-            write!(f, "\n")?;
+            writeln!(f)?;
             for c in row.iter() {
                 write!(f, "{}", c)?;
             }
@@ -381,6 +770,9 @@ impl fmt::Display for Layout {
 struct CharMap {
     pos_to_char: HashMap<XY, char>,
     char_to_pos: HashMap<char, Vec<XY>>,
+    /// How many words cover the position. The crossings are covered by more than one word. Needed to tell if
+    /// the letter stays when a word is removed.
+    pos_to_count: HashMap<XY, usize>,
     top_left: Option<XY>,
     /// Bottom-right corner inside the layout rectangle.
     bottom_right: Option<XY>,
@@ -403,6 +795,7 @@ impl CharMap {
         CharMap {
             pos_to_char: HashMap::new(),
             char_to_pos: HashMap::new(),
+            pos_to_count: HashMap::new(),
             top_left: None,
             bottom_right: None,
             has_conflict: false,
@@ -411,7 +804,7 @@ impl CharMap {
 
     fn insert_word(&mut self, word: &str, pos: XY, orient: Orientation) -> LayoutResult<usize> {
         let mut pos = pos;
-        let mut is_conflict = false;
+        let mut conflict = None;
         let mut crossing_count: usize = 0;
         for curr_char in word.chars() {
             match self.insert_char(pos, curr_char) {
@@ -419,28 +812,36 @@ impl CharMap {
                     CharInsertResult::First => (),
                     CharInsertResult::Taken => crossing_count += 1,
                 },
-                Err(_) => is_conflict = true,
+                Err(err) => {
+                    conflict.get_or_insert(err);
+                }
             }
             pos = pos + orient.step();
         }
-        match is_conflict {
-            true => Err(()),
-            false => Ok(crossing_count),
+        match conflict {
+            Some(err) => Err(err),
+            None => Ok(crossing_count),
         }
     }
 
     fn insert_char(&mut self, pos: XY, curr_char: char) -> LayoutResult<CharInsertResult> {
+        *self.pos_to_count.entry(pos).or_default() += 1;
         match self.pos_to_char.get(&pos) {
             Some(char_at_pos) => {
                 if *char_at_pos == curr_char {
                     // Same char, all good, carry on.
                     Ok(CharInsertResult::Taken)
                 } else {
+                    let existing = *char_at_pos;
                     self.has_conflict = true;
                     self.pos_to_char.insert(pos, CONFLICT_CHAR);
                     // On conflict, char_to_pos breaks. It does not strictly reflect the positions of the characters.
                     self.update_corners(pos);
-                    Err(())
+                    Err(LayoutError::ConflictingLetters {
+                        pos,
+                        existing,
+                        new: curr_char,
+                    })
                 }
             }
             None => {
@@ -459,6 +860,49 @@ impl CharMap {
         }
     }
 
+    /// Remove a word that was inserted before. Must not be called if there is a conflict.
+    ///
+    /// # Returns
+    /// The number of crossings the word had, i.e. the letters that stay because other words cover them.
+    fn remove_word(&mut self, word: &str, pos: XY, orient: Orientation) -> usize {
+        assert!(!self.has_conflict);
+        let mut pos = pos;
+        let mut crossing_count: usize = 0;
+        for _ in word.chars() {
+            if self.remove_char(pos) {
+                crossing_count += 1;
+            }
+            pos = pos + orient.step();
+        }
+        self.top_left = None;
+        self.bottom_right = None;
+        let positions: Vec<XY> = self.pos_to_char.keys().copied().collect();
+        for pos in positions {
+            self.update_corners(pos);
+        }
+        crossing_count
+    }
+
+    /// Remove one word's letter from the position. Return true if the letter stays because other word covers it.
+    fn remove_char(&mut self, pos: XY) -> bool {
+        let count = self
+            .pos_to_count
+            .get_mut(&pos)
+            .expect("removing a letter that is not on the map");
+        *count -= 1;
+        if *count > 0 {
+            return true;
+        }
+        self.pos_to_count.remove(&pos);
+        let c = self.pos_to_char.remove(&pos).unwrap();
+        let positions_for_char = self.char_to_pos.get_mut(&c).unwrap();
+        positions_for_char.retain(|p| *p != pos);
+        if positions_for_char.is_empty() {
+            self.char_to_pos.remove(&c);
+        }
+        false
+    }
+
     fn update_corners(&mut self, pos: XY) {
         self.top_left = Some(if let Some(top_left) = self.top_left {
             XY {
@@ -488,6 +932,8 @@ impl CharMap {
         let bottom_right = self.bottom_right.unwrap();
         for (pos, c) in self.pos_to_char.iter() {
             let _ = grid.insert_char(*pos - top_left, *c);
+            grid.pos_to_count
+                .insert(*pos - top_left, self.pos_to_count[pos]);
         }
         assert_eq!(grid.top_left.unwrap(), XY { x: 0, y: 0 });
         assert_eq!(grid.bottom_right.unwrap(), bottom_right - top_left);
@@ -517,6 +963,7 @@ impl CharMap {
 mod tests {
     use super::Layout;
     use super::Orientation;
+    use super::{LayoutError, SizeLimit, XY};
 
     #[test]
     fn test_insert_and_display() {
@@ -539,7 +986,17 @@ mod tests {
     fn test_insert_conflict() {
         let mut layout = Layout::new();
         let _ = layout.insert_at("xab", (0, 0), Orientation::Horiz);
-        let _ = layout.insert_at("xyz", (2, 0), Orientation::Vert);
+        let err = layout
+            .insert_at("xyz", (2, 0), Orientation::Vert)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            LayoutError::ConflictingLetters {
+                pos: XY { x: 2, y: 0 },
+                existing: 'b',
+                new: 'x',
+            }
+        );
         let formatted = format!("{:_>0}", layout);
         eprintln!("{}", formatted);
         let expected = "
@@ -551,6 +1008,39 @@ mod tests {
         assert_eq!(formatted, expected)
     }
 
+    #[test]
+    fn test_insert_invalid_word() {
+        let mut layout = Layout::new();
+        let err = layout.insert_at("", (0, 0), Orientation::Horiz);
+        assert_eq!(err, Err(LayoutError::EmptyWord));
+        let err = layout.insert_at("ice cream", (0, 0), Orientation::Horiz);
+        assert_eq!(
+            err,
+            Err(LayoutError::InvalidChars {
+                word: "ice cream".to_owned(),
+                chars: vec![' '],
+            })
+        );
+        assert!(layout.get_word_positions().is_empty());
+
+        layout.insert_at("abc", (0, 0), Orientation::Horiz).unwrap();
+        assert_eq!(
+            layout.find_placement_conflict("xyz", XY { x: 0, y: 1 }, Orientation::Horiz),
+            Some(LayoutError::EnvelopeOverlap {
+                pos: XY { x: 0, y: 0 }
+            })
+        );
+        // The same word on top of itself, or a word running along another one.
+        for (word, x) in [("abc", 0), ("bc", 1)] {
+            assert_eq!(
+                layout.find_placement_conflict(word, XY { x, y: 0 }, Orientation::Horiz),
+                Some(LayoutError::EnvelopeOverlap {
+                    pos: XY { x, y: 0 }
+                })
+            );
+        }
+    }
+
     #[test]
     fn test_count_crossings() {
         let mut layout = Layout::new();
@@ -592,4 +1082,171 @@ mod tests {
 
         assert_eq!(formatted1, formatted2)
     }
+
+    #[test]
+    fn test_remove_word() {
+        let mut layout = Layout::new();
+        layout.insert_at("abc", (0, 0), Orientation::Horiz).unwrap();
+        layout.insert_at("abc", (0, 0), Orientation::Vert).unwrap();
+        layout.insert_at("cba", (2, 0), Orientation::Vert).unwrap();
+        layout
+            .insert_at("cxaz", (0, 2), Orientation::Horiz)
+            .unwrap();
+        assert_eq!(layout.n_crossings, 4);
+        assert_eq!(layout.area(), 12);
+
+        let removed = layout.remove_word(3);
+        assert_eq!(removed.word, "cxaz");
+        assert_eq!(layout.n_crossings, 2);
+        assert_eq!(layout.area(), 9);
+        let expected = "
+            abc
+            b_b
+            c_a
+        ";
+        assert_eq!(format!("{:_>0}", layout), expected.trim().replace(" ", ""));
+
+        layout.remove_word(0);
+        assert_eq!(layout.n_crossings, 0);
+        let expected = "
+            a_c
+            b_b
+            c_a
+        ";
+        assert_eq!(format!("{:_>0}", layout), expected.trim().replace(" ", ""));
+        assert_eq!(layout.char_map.char_to_pos[&'c'].len(), 2);
+        assert!(!layout.char_map.char_to_pos.contains_key(&'x'));
+
+        layout.remove_word(1);
+        layout.remove_word(0);
+        assert!(layout.char_map.is_empty());
+        assert_eq!(layout.area(), 0);
+    }
+
+    #[test]
+    fn test_remove_word_with_conflict() {
+        let mut layout = Layout::new();
+        layout.insert_at("xab", (0, 0), Orientation::Horiz).unwrap();
+        let _ = layout.insert_at("xyz", (2, 0), Orientation::Vert);
+        layout.remove_word(1);
+        assert!(!layout.char_map.has_conflict);
+        assert_eq!(format!("{:_>0}", layout), "xab");
+    }
+
+    #[test]
+    fn test_density() {
+        let mut layout = Layout::new();
+        assert_eq!(layout.density(), 0.0);
+        layout.insert_at("abc", (0, 0), Orientation::Horiz).unwrap();
+        assert_eq!(layout.density(), 1.0);
+        layout.insert_at("abc", (0, 0), Orientation::Vert).unwrap();
+        assert_eq!(layout.density(), 5.0 / 9.0);
+    }
+
+    #[test]
+    fn test_positions_within_size_limit() {
+        let mut layout = Layout::new();
+        layout.insert_at("abc", (0, 0), Orientation::Horiz).unwrap();
+        assert_eq!(layout.dimensions(), XY { x: 3, y: 1 });
+        assert_eq!(
+            layout.dimensions_with("xbyy", XY { x: 1, y: -1 }, Orientation::Vert),
+            XY { x: 3, y: 4 }
+        );
+        let limit = SizeLimit {
+            max_width: None,
+            max_height: Some(3),
+        };
+        assert_eq!(layout.get_possible_start_positions("xay").len(), 1);
+        assert_eq!(
+            layout
+                .get_possible_start_positions_within("xay", &limit)
+                .len(),
+            1
+        );
+        assert_eq!(layout.get_possible_start_positions("xbyy").len(), 1);
+        assert!(
+            layout
+                .get_possible_start_positions_within("xbyy", &limit)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        let mut layout1 = Layout::new();
+        layout1
+            .insert_at("xab", (0, 0), Orientation::Horiz)
+            .unwrap();
+        layout1.insert_at("xyz", (0, 0), Orientation::Vert).unwrap();
+
+        let mut translated = Layout::new();
+        translated
+            .insert_at("xab", (3, 5), Orientation::Horiz)
+            .unwrap();
+        translated
+            .insert_at("xyz", (3, 5), Orientation::Vert)
+            .unwrap();
+        assert_eq!(layout1.distance(&translated), 0.0);
+
+        let mut transposed = Layout::new();
+        transposed
+            .insert_at("xab", (0, 0), Orientation::Vert)
+            .unwrap();
+        transposed
+            .insert_at("xyz", (0, 0), Orientation::Horiz)
+            .unwrap();
+        assert_eq!(layout1.distance(&transposed), 0.0);
+
+        let mut other = Layout::new();
+        other.insert_at("xab", (0, 0), Orientation::Horiz).unwrap();
+        other.insert_at("byz", (2, 0), Orientation::Vert).unwrap();
+        // Only "xab" is common, 3 letters out of 7.
+        assert!((layout1.distance(&other) - 4.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_is_connected() {
+        let mut layout = Layout::new();
+        assert!(layout.is_connected());
+        layout.insert_at("abc", (0, 0), Orientation::Horiz).unwrap();
+        layout.insert_at("cde", (2, 0), Orientation::Vert).unwrap();
+        assert!(layout.is_connected());
+        layout.insert_at("xgz", (4, 1), Orientation::Vert).unwrap();
+        assert!(!layout.is_connected());
+        layout.insert_at("efg", (2, 2), Orientation::Horiz).unwrap();
+        assert!(layout.is_connected());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut layout = Layout::new();
+        layout.insert_at("abc", (0, 0), Orientation::Horiz).unwrap();
+        layout.insert_at("cde", (2, 0), Orientation::Vert).unwrap();
+        assert_eq!(layout.validate(), vec![]);
+        assert_eq!(layout.char_at((2, 1)), Some('d'));
+        assert_eq!(layout.char_at((1, 1)), None);
+
+        // Side by side with "cde", and not crossing anything.
+        layout.insert_at("xy", (3, 1), Orientation::Vert).unwrap();
+        assert_eq!(
+            layout.validate(),
+            vec![
+                (2, LayoutError::EnvelopeOverlap { pos: (2, 1).into() }),
+                (2, LayoutError::Disconnected)
+            ]
+        );
+        layout.remove_word(2);
+        assert!(layout.insert_at("zz", (2, 0), Orientation::Horiz).is_err());
+        assert_eq!(
+            layout.validate()[0],
+            (
+                2,
+                LayoutError::ConflictingLetters {
+                    pos: (2, 0).into(),
+                    existing: 'c',
+                    new: 'z'
+                }
+            )
+        );
+    }
 }
@@ -1,4 +1,16 @@
 pub mod generator;
+#[cfg(feature = "serde")]
+pub mod json;
 pub mod layout;
+pub mod optimizer;
+pub mod puzzle;
+pub mod scorer;
+pub mod trace;
 pub use generator::*;
+#[cfg(feature = "serde")]
+pub use json::*;
 pub use layout::*;
+pub use optimizer::*;
+pub use puzzle::*;
+pub use scorer::*;
+pub use trace::*;
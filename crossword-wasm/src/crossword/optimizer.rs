@@ -0,0 +1,217 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{Area, Crossings, FittedArea, Layout, Scorer, SizeLimit, WeightedSum, WordPosition};
+
+/// Prefer compact layouts. Each field of the bounding rectangle costs a point, each crossing is worth a few.
+pub fn compactness_scorer() -> Box<dyn Scorer> {
+    Box::new(WeightedSum(vec![
+        (4.0, Box::new(Crossings)),
+        (1.0, Box::new(Area)),
+    ]))
+}
+
+/// Like [compactness_scorer], but prefer the layouts with the given width to height ratio, see [FittedArea].
+pub fn fitted_compactness_scorer(target: f64) -> Box<dyn Scorer> {
+    Box::new(WeightedSum(vec![
+        (4.0, Box::new(Crossings)),
+        (1.0, Box::new(FittedArea(target))),
+    ]))
+}
+
+/// Improves a finished layout with simulated annealing. Each step moves a single word, either to any other
+/// position where it fits, or rotates it around one of its crossings. Moves that make the score worse are
+/// still accepted, with probability falling with the temperature, so the search can get out of the local
+/// optimum that the greedy generator ends in.
+pub struct Annealer {
+    pub iterations: usize,
+    /// Temperature at the first step. The temperature falls geometrically down to the final temperature.
+    pub initial_temperature: f64,
+    pub final_temperature: f64,
+    /// The same seed always gives the same result.
+    pub seed: u64,
+    /// Annealing needs a numeric score, so the lexicographic scorers only use their first criterion.
+    pub scorer: Box<dyn Scorer>,
+    /// The moved words must keep the layout within the limit.
+    pub size_limit: SizeLimit,
+    /// The words that are never moved, e.g. the pinned ones.
+    pub pinned: Vec<String>,
+}
+
+impl Default for Annealer {
+    fn default() -> Self {
+        Annealer {
+            iterations: 2000,
+            initial_temperature: 10.0,
+            final_temperature: 0.1,
+            seed: 0,
+            scorer: compactness_scorer(),
+            size_limit: SizeLimit::default(),
+            pinned: Vec::new(),
+        }
+    }
+}
+
+impl Annealer {
+    /// Return the best layout seen during the annealing. It has the same words as the input layout, and is
+    /// never worse than the input layout.
+    pub fn optimize(&self, layout: &Layout) -> Layout {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut current = layout.clone();
+        let mut current_score = self.scorer.score(&current);
+        let mut best = current.clone();
+        let mut best_score = current_score;
+
+        if layout.get_word_positions().len() < 2 {
+            return best.normalize();
+        }
+
+        let cooling = self.final_temperature / self.initial_temperature;
+        for step in 0..self.iterations {
+            let temperature =
+                self.initial_temperature * cooling.powf(step as f64 / self.iterations as f64);
+            let Some(candidate) = random_move(&current, &self.pinned, &self.size_limit, &mut rng)
+            else {
+                continue;
+            };
+            let candidate_score = self.scorer.score(&candidate);
+            let delta = candidate_score - current_score;
+            if delta >= 0.0 || rng.gen_bool((delta / temperature).exp().min(1.0)) {
+                current = candidate;
+                current_score = candidate_score;
+                if current_score > best_score {
+                    best = current.clone();
+                    best_score = current_score;
+                }
+            }
+        }
+        best.normalize()
+    }
+}
+
+/// Take a random word that is not pinned out of the layout and put it back elsewhere. Return None if the word
+/// has nowhere else to go, or if the move would split the crossword.
+fn random_move(
+    layout: &Layout,
+    pinned: &[String],
+    size_limit: &SizeLimit,
+    rng: &mut ChaCha8Rng,
+) -> Option<Layout> {
+    let movable: Vec<usize> = (0..layout.get_word_positions().len())
+        .filter(|&i| !pinned.contains(&layout.get_word_positions()[i].word))
+        .collect();
+    if movable.is_empty() {
+        return None;
+    }
+    let mut rest = layout.clone();
+    let moved = rest.remove_word(movable[rng.gen_range(0..movable.len())]);
+
+    let mut positions = rest.get_possible_start_positions_within(&moved.word, size_limit);
+    positions.retain(|(pos, orientation)| (*pos, *orientation) != (moved.pos, moved.orientation));
+    if rng.gen_bool(0.5) {
+        // Rotate the word around one of its letters, so the crossing on that letter stays in place.
+        positions.retain(|(pos, orientation)| {
+            let rotated = WordPosition {
+                word: moved.word.clone(),
+                pos: *pos,
+                orientation: *orientation,
+            };
+            *orientation != moved.orientation
+                && rotated.cells().zip(moved.cells()).any(|(a, b)| a == b)
+        });
+    }
+    if positions.is_empty() {
+        return None;
+    }
+
+    let (pos, orientation) = positions[rng.gen_range(0..positions.len())];
+    if rest.insert_at(&moved.word, pos, orientation).is_err() {
+        return None;
+    }
+    if rest.is_connected() {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Annealer, compactness_scorer, fitted_compactness_scorer};
+    use crate::crossword::generator::tests::{ENGLISH_WORDS, POLISH_WORDS};
+    use crate::{GeneratorMode, Layout, generate_crossword};
+
+    #[test]
+    fn test_annealing_keeps_words_and_does_not_worsen_score() {
+        let words = ENGLISH_WORDS;
+        let layout = generate_crossword(&words, GeneratorMode::Automatic).layout;
+        let optimized = Annealer::default().optimize(&layout);
+        eprintln!("before:\n{layout}\n\nafter:\n{optimized}");
+        assert_eq!(
+            optimized.get_word_positions().len(),
+            layout.get_word_positions().len()
+        );
+        assert!(optimized.is_connected());
+        let scorer = compactness_scorer();
+        assert!(scorer.score(&optimized) >= scorer.score(&layout));
+    }
+
+    #[test]
+    fn test_annealing_with_aspect_ratio() {
+        let words = ENGLISH_WORDS;
+        let layout = generate_crossword(&words, GeneratorMode::Automatic).layout;
+        let wide = Annealer {
+            scorer: fitted_compactness_scorer(4.0),
+            ..Annealer::default()
+        }
+        .optimize(&layout);
+        let high = Annealer {
+            scorer: fitted_compactness_scorer(0.25),
+            ..Annealer::default()
+        }
+        .optimize(&layout);
+        eprintln!("wide:\n{wide}\n\nhigh:\n{high}");
+        let ratio = |layout: &Layout| {
+            let dim = layout.dimensions();
+            dim.x as f64 / dim.y as f64
+        };
+        assert!(ratio(&wide) > ratio(&high));
+    }
+
+    #[test]
+    fn test_annealing_is_deterministic() {
+        let words = POLISH_WORDS;
+        let layout = generate_crossword(&words, GeneratorMode::Automatic).layout;
+        let annealer = Annealer {
+            seed: 42,
+            ..Annealer::default()
+        };
+        let first = annealer.optimize(&layout);
+        let second = annealer.optimize(&layout);
+        assert_eq!(format!("{first}"), format!("{second}"));
+    }
+
+    #[test]
+    fn test_annealing_keeps_pinned_words() {
+        let words = POLISH_WORDS;
+        let layout = generate_crossword(&words, GeneratorMode::Automatic).layout;
+        // Where zlepk is relative to burak.
+        let offset = |layout: &Layout| {
+            let find = |word| {
+                let words = layout.get_word_positions();
+                words.iter().find(|wp| wp.word == word).unwrap().clone()
+            };
+            let (burak, zlepk) = (find("burak"), find("zlepk"));
+            (zlepk.pos - burak.pos, burak.orientation, zlepk.orientation)
+        };
+        for seed in 0..20 {
+            let annealer = Annealer {
+                seed,
+                pinned: vec!["burak".to_owned(), "zlepk".to_owned()],
+                ..Annealer::default()
+            };
+            let optimized = annealer.optimize(&layout);
+            assert_eq!(offset(&optimized), offset(&layout));
+        }
+    }
+}
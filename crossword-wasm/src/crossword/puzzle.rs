@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+
+use super::{Layout, LayoutError, Orientation, WordPosition};
+
+/// A crossword to solve: the layout, the clues and who made it.
+///
+/// Written and read as words text, one word per line:
+///
+/// ```text
+/// # title: Animals
+/// # author: Jan Kowalski
+/// burak 0 0 hor A red root vegetable
+/// kark 4 0 ver The back of the neck
+/// zlepek A clue of a word that is not on the layout yet
+/// ```
+///
+/// The "# key: value" lines are the [PuzzleMetadata], other lines starting with "#" are comments. A word
+/// with "x y hor|ver" is on the layout at that position, the rest of the line is the clue.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Puzzle {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: PuzzleMetadata,
+    pub layout: Layout,
+    /// The clue per word. It can have clues of the words that are not on the layout, e.g. the dropped ones, so
+    /// they are not lost when the layout is generated again.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub clues: BTreeMap<String, String>,
+}
+
+/// Who made the puzzle and when. Unset fields are not written.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct PuzzleMetadata {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub title: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub author: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub copyright: Option<String>,
+    /// Free form, e.g. "2025-03-01".
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub date: Option<String>,
+    /// The language of the words, e.g. "pl" or "en".
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub language: Option<String>,
+    /// Anything else, it can have many lines.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub notes: Option<String>,
+}
+
+#[wasm_bindgen]
+impl PuzzleMetadata {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PuzzleMetadata {
+        PuzzleMetadata::default()
+    }
+}
+
+impl PuzzleMetadata {
+    /// The fields with their keys in the words text.
+    fn fields(&self) -> [(&'static str, &Option<String>); 6] {
+        [
+            ("title", &self.title),
+            ("author", &self.author),
+            ("copyright", &self.copyright),
+            ("date", &self.date),
+            ("language", &self.language),
+            ("notes", &self.notes),
+        ]
+    }
+
+    fn field_mut(&mut self, key: &str) -> Option<&mut Option<String>> {
+        match key {
+            "title" => Some(&mut self.title),
+            "author" => Some(&mut self.author),
+            "copyright" => Some(&mut self.copyright),
+            "date" => Some(&mut self.date),
+            "language" => Some(&mut self.language),
+            "notes" => Some(&mut self.notes),
+            _ => None,
+        }
+    }
+}
+
+impl Puzzle {
+    pub fn new(layout: Layout) -> Puzzle {
+        Puzzle {
+            layout,
+            ..Puzzle::default()
+        }
+    }
+
+    pub fn clue(&self, word: &str) -> Option<&str> {
+        self.clues.get(word).map(|c| c.as_str())
+    }
+
+    /// Set the clue of the word, an empty clue removes it.
+    pub fn set_clue(&mut self, word: &str, clue: &str) {
+        if clue.trim().is_empty() {
+            self.clues.remove(word);
+        } else {
+            self.clues.insert(word.to_owned(), clue.to_owned());
+        }
+    }
+
+    /// The puzzle as words text, see [Puzzle]. The words on the layout come first, in the order of
+    /// insertion, then the words that only have a clue.
+    pub fn to_words_text(&self) -> String {
+        let mut text = String::new();
+        for (key, value) in self.metadata.fields() {
+            if let Some(value) = value {
+                for line in value.lines() {
+                    writeln!(text, "# {key}: {line}").unwrap();
+                }
+            }
+        }
+        let words = self.layout.get_word_positions();
+        for wp in words {
+            let orientation = match wp.orientation {
+                Orientation::Horiz => "hor",
+                Orientation::Vert => "ver",
+            };
+            write!(
+                text,
+                "{} {} {} {}",
+                wp.word, wp.pos.x, wp.pos.y, orientation
+            )
+            .unwrap();
+            self.write_clue(&mut text, &wp.word);
+        }
+        for word in self.clues.keys() {
+            if !words.iter().any(|wp| wp.word == *word) {
+                text.push_str(word);
+                self.write_clue(&mut text, word);
+            }
+        }
+        text
+    }
+
+    fn write_clue(&self, text: &mut String, word: &str) {
+        if let Some(clue) = self.clue(word) {
+            let clue: Vec<&str> = clue.split_whitespace().collect();
+            write!(text, " {}", clue.join(" ")).unwrap();
+        }
+        text.push('\n');
+    }
+
+    /// Read the words text written by [Puzzle::to_words_text]. Return the puzzle with the words that have a
+    /// position on its layout, and the other words in the order of the text.
+    pub fn from_words_text(text: &str) -> Result<(Puzzle, Vec<String>), String> {
+        let mut puzzle = Puzzle::default();
+        let mut words = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(comment) = line.strip_prefix('#') {
+                let Some((key, value)) = comment.split_once(':') else {
+                    continue;
+                };
+                if let Some(field) = puzzle.metadata.field_mut(key.trim()) {
+                    let value = value.trim();
+                    match field {
+                        Some(lines) => *lines = format!("{lines}\n{value}"),
+                        None => *field = Some(value.to_owned()),
+                    }
+                }
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let clue = match parse_position(&fields) {
+                Some(wp) => {
+                    // Conflicting words are kept, for Layout::validate to report.
+                    match puzzle.layout.insert_at(&wp.word, wp.pos, wp.orientation) {
+                        Ok(()) | Err(LayoutError::ConflictingLetters { .. }) => (),
+                        Err(err) => return Err(format!("cannot pin {}: {err}", wp.word)),
+                    }
+                    &fields[4..]
+                }
+                None => {
+                    words.push(fields[0].to_owned());
+                    &fields[1..]
+                }
+            };
+            puzzle.set_clue(fields[0], &clue.join(" "));
+        }
+        Ok((puzzle, words))
+    }
+}
+
+/// The "word x y hor|ver" at the start of the line, if it is there.
+fn parse_position(fields: &[&str]) -> Option<WordPosition> {
+    match fields {
+        [word, x, y, orientation, ..] => Some(WordPosition {
+            word: word.to_string(),
+            pos: (x.parse::<i32>().ok()?, y.parse::<i32>().ok()?).into(),
+            orientation: orientation.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Puzzle;
+    use crate::crossword::generator::tests::POLISH_WORDS;
+    use crate::{CrosswordGenerator, GeneratorMode};
+
+    #[test]
+    fn test_words_text() {
+        let text = "# title: Animals\n\
+            # a comment\n\
+            # notes: first\n\
+            # notes: second\n\
+            burak 0 0 hor A red   root\n\
+            kark 4 0 ver\n\
+            zlepk Lumps\n";
+        let (puzzle, words) = Puzzle::from_words_text(text).unwrap();
+        assert_eq!(puzzle.metadata.title.as_deref(), Some("Animals"));
+        assert_eq!(puzzle.metadata.notes.as_deref(), Some("first\nsecond"));
+        assert_eq!(puzzle.layout.words(), vec!["burak", "kark"]);
+        assert_eq!(words, vec!["zlepk"]);
+        assert_eq!(puzzle.clue("burak"), Some("A red root"));
+        assert_eq!(puzzle.clue("kark"), None);
+        assert_eq!(puzzle.clue("zlepk"), Some("Lumps"));
+        assert_eq!(
+            puzzle.to_words_text(),
+            "# title: Animals\n\
+            # notes: first\n\
+            # notes: second\n\
+            burak 0 0 hor A red root\n\
+            kark 4 0 ver\n\
+            zlepk Lumps\n"
+        );
+
+        let (conflicting, _) = Puzzle::from_words_text("burak 0 0 hor\nbacz 0 0 hor").unwrap();
+        assert_eq!(conflicting.layout.words(), vec!["burak", "bacz"]);
+        assert!(!conflicting.layout.validate().is_empty());
+        let (reread, _) = Puzzle::from_words_text(&conflicting.to_words_text()).unwrap();
+        assert_eq!(
+            reread.layout.get_word_positions(),
+            conflicting.layout.get_word_positions()
+        );
+        assert!(Puzzle::from_words_text("bu\u{7}rak 0 0 hor").is_err());
+    }
+
+    #[test]
+    fn test_keep_clues() {
+        let words = POLISH_WORDS;
+        let generator = CrosswordGenerator::for_mode(GeneratorMode::Automatic);
+        let (mut puzzle, words) = Puzzle::from_words_text(&words.join(" clue\n")).unwrap();
+        let words: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+        puzzle.layout = generator
+            .generate_crossword(&words, GeneratorMode::Automatic)
+            .layout;
+        let (read, free_words) = Puzzle::from_words_text(&puzzle.to_words_text()).unwrap();
+        assert!(free_words.is_empty());
+        assert_eq!(
+            read.layout.get_word_positions(),
+            puzzle.layout.get_word_positions()
+        );
+        assert_eq!(read.clues, puzzle.clues);
+        assert_eq!(read.clues.len(), 3);
+    }
+}
@@ -0,0 +1,332 @@
+use std::cmp;
+
+use super::Layout;
+
+/// Width to height ratio of a square.
+pub const ASPECT_RATIO_SQUARE: f64 = 1.0;
+/// Width to height ratio of an A4 sheet in portrait orientation.
+pub const ASPECT_RATIO_A4_PORTRAIT: f64 = 210.0 / 297.0;
+
+/// Tells how good a layout is. The generator uses it to choose among the candidate layouts.
+///
+/// Scorers are combined into recipes, either as a [WeightedSum] of the scores, or [Lexicographic], where the
+/// next scorer only breaks the ties of the previous one. A recipe can be written as a string, see
+/// [parse_scorer].
+pub trait Scorer {
+    /// The score of the layout. Larger is better.
+    fn score(&self, layout: &Layout) -> f64;
+
+    /// Compare two layouts. Greater means that the first layout is better than the other.
+    fn compare(&self, some: &Layout, other: &Layout) -> cmp::Ordering {
+        self.score(some).total_cmp(&self.score(other))
+    }
+
+    /// The recipe of the scorer, such that [parse_scorer] gives back the same scorer.
+    fn recipe(&self) -> String;
+}
+
+/// More crossings is better.
+pub struct Crossings;
+
+impl Scorer for Crossings {
+    fn score(&self, layout: &Layout) -> f64 {
+        layout.crossings_count() as f64
+    }
+
+    fn recipe(&self) -> String {
+        "crossings".to_owned()
+    }
+}
+
+/// Smaller area is better.
+pub struct Area;
+
+impl Scorer for Area {
+    fn score(&self, layout: &Layout) -> f64 {
+        -(layout.area() as f64)
+    }
+
+    fn recipe(&self) -> String {
+        "area".to_owned()
+    }
+}
+
+/// Less empty space is better, see [Layout::density]. Unlike the crossings alone, does not reward tangled
+/// layouts that are mostly empty.
+pub struct Density;
+
+impl Scorer for Density {
+    fn score(&self, layout: &Layout) -> f64 {
+        layout.density()
+    }
+
+    fn recipe(&self) -> String {
+        "density".to_owned()
+    }
+}
+
+/// More placed words is better.
+pub struct WordCount;
+
+impl Scorer for WordCount {
+    fn score(&self, layout: &Layout) -> f64 {
+        layout.get_word_positions().len() as f64
+    }
+
+    fn recipe(&self) -> String {
+        "words".to_owned()
+    }
+}
+
+/// The width to height ratio closer to the target is better, e.g. [ASPECT_RATIO_SQUARE]. Being twice as
+/// wide is as bad as being twice as high.
+pub struct AspectRatio(pub f64);
+
+impl Scorer for AspectRatio {
+    fn score(&self, layout: &Layout) -> f64 {
+        let dim = layout.dimensions();
+        if dim.x == 0 || dim.y == 0 {
+            return 0.0;
+        }
+        -((dim.x as f64 / dim.y as f64) / self.0).ln().abs()
+    }
+
+    fn recipe(&self) -> String {
+        format!("aspect_ratio({})", self.0)
+    }
+}
+
+/// Smaller area of the smallest rectangle of the target width to height ratio that encloses the layout is
+/// better. Like [Area], but long thin layouts are worse than compact ones of the same area.
+pub struct FittedArea(pub f64);
+
+impl Scorer for FittedArea {
+    fn score(&self, layout: &Layout) -> f64 {
+        let dim = layout.dimensions();
+        let width = f64::max(dim.x as f64, dim.y as f64 * self.0);
+        -(width * width / self.0)
+    }
+
+    fn recipe(&self) -> String {
+        format!("fitted_area({})", self.0)
+    }
+}
+
+/// The sum of the weighted scores.
+pub struct WeightedSum(pub Vec<(f64, Box<dyn Scorer>)>);
+
+impl Scorer for WeightedSum {
+    fn score(&self, layout: &Layout) -> f64 {
+        self.0.iter().map(|(w, s)| w * s.score(layout)).sum()
+    }
+
+    fn recipe(&self) -> String {
+        let terms: Vec<String> = self
+            .0
+            .iter()
+            .map(|(w, s)| format!("{w}*{}", s.recipe()))
+            .collect();
+        terms.join("+")
+    }
+}
+
+/// Compare with the first scorer, and use the next scorers only if the previous ones tell the layouts are
+/// equal.
+pub struct Lexicographic(pub Vec<Box<dyn Scorer>>);
+
+impl Scorer for Lexicographic {
+    /// The score of the first scorer. Use [Scorer::compare] to rank the layouts.
+    fn score(&self, layout: &Layout) -> f64 {
+        self.0.first().map_or(0.0, |s| s.score(layout))
+    }
+
+    fn compare(&self, some: &Layout, other: &Layout) -> cmp::Ordering {
+        for scorer in &self.0 {
+            match scorer.compare(some, other) {
+                cmp::Ordering::Equal => (),
+                ordering => return ordering,
+            }
+        }
+        cmp::Ordering::Equal
+    }
+
+    fn recipe(&self) -> String {
+        let scorers: Vec<String> = self.0.iter().map(|s| s.recipe()).collect();
+        scorers.join(",")
+    }
+}
+
+/// The names of the built-in scorers accepted by [parse_scorer].
+pub const SCORER_NAMES: [&str; 6] = [
+    "crossings",
+    "area",
+    "density",
+    "words",
+    "aspect_ratio",
+    "fitted_area",
+];
+
+/// Get a built-in scorer by name. The aspect ratio scorers take the target ratio as the argument, and use
+/// [ASPECT_RATIO_SQUARE] without one. The ratio must be positive.
+pub fn scorer_by_name(name: &str, arg: Option<f64>) -> Option<Box<dyn Scorer>> {
+    let target = arg.unwrap_or(ASPECT_RATIO_SQUARE);
+    if !is_valid_ratio(target) {
+        return None;
+    }
+    match name {
+        "crossings" => Some(Box::new(Crossings)),
+        "area" => Some(Box::new(Area)),
+        "density" => Some(Box::new(Density)),
+        "words" => Some(Box::new(WordCount)),
+        "aspect_ratio" => Some(Box::new(AspectRatio(target))),
+        "fitted_area" => Some(Box::new(FittedArea(target))),
+        _ => None,
+    }
+}
+
+/// A width to height ratio that the aspect ratio scorers can score against.
+pub fn is_valid_ratio(ratio: f64) -> bool {
+    ratio.is_finite() && ratio > 0.0
+}
+
+/// Parse a scoring recipe. The recipe is a comma separated list of criteria compared lexicographically. Each
+/// criterion is a sum of the built-in scorers from [SCORER_NAMES], optionally with a weight and an argument.
+///
+/// ```text
+/// crossings,area                 more crossings, then smaller area
+/// 4*crossings+area               a crossing is worth 4 fields of area
+/// crossings,fitted_area(0.707)   more crossings, then fits better on A4 portrait
+/// ```
+pub fn parse_scorer(recipe: &str) -> Result<Box<dyn Scorer>, String> {
+    let mut criteria: Vec<Box<dyn Scorer>> = recipe
+        .split(',')
+        .map(parse_weighted_sum)
+        .collect::<Result<_, _>>()?;
+    if criteria.len() == 1 {
+        Ok(criteria.remove(0))
+    } else {
+        Ok(Box::new(Lexicographic(criteria)))
+    }
+}
+
+fn parse_weighted_sum(criterion: &str) -> Result<Box<dyn Scorer>, String> {
+    let mut terms: Vec<(Option<f64>, Box<dyn Scorer>)> = criterion
+        .split('+')
+        .map(parse_term)
+        .collect::<Result<_, _>>()?;
+    if let [(None, _)] = terms[..] {
+        return Ok(terms.remove(0).1);
+    }
+    Ok(Box::new(WeightedSum(
+        terms
+            .into_iter()
+            .map(|(w, s)| (w.unwrap_or(1.0), s))
+            .collect(),
+    )))
+}
+
+/// Parse "[weight*]name[(arg)]".
+fn parse_term(term: &str) -> Result<(Option<f64>, Box<dyn Scorer>), String> {
+    let term = term.trim();
+    let bad_number = |s: &str| format!("bad number in scoring recipe: {s:?}");
+    let (weight, scorer) = match term.split_once('*') {
+        Some((weight, scorer)) => {
+            let weight: f64 = weight.trim().parse().map_err(|_| bad_number(weight))?;
+            (Some(weight), scorer.trim())
+        }
+        None => (None, term),
+    };
+    let (name, arg) = match scorer.split_once('(') {
+        Some((name, arg)) => {
+            let arg = arg
+                .strip_suffix(')')
+                .ok_or(format!("missing ')' in scoring recipe: {scorer:?}"))?;
+            let arg: f64 = arg.trim().parse().map_err(|_| bad_number(arg))?;
+            if !is_valid_ratio(arg) {
+                return Err(format!("the ratio must be positive: {scorer:?}"));
+            }
+            (name.trim(), Some(arg))
+        }
+        None => (scorer, None),
+    };
+    let scorer = scorer_by_name(name, arg).ok_or(format!(
+        "unknown scorer {name:?}, expected one of {}",
+        SCORER_NAMES.join(", ")
+    ))?;
+    Ok((weight, scorer))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp;
+
+    use super::{
+        ASPECT_RATIO_SQUARE, Area, AspectRatio, Density, FittedArea, Scorer, parse_scorer,
+        scorer_by_name,
+    };
+    use crate::{Layout, Orientation};
+
+    #[test]
+    fn test_aspect_ratio() {
+        let mut square = Layout::new();
+        square.insert_at("abc", (0, 0), Orientation::Horiz).unwrap();
+        square.insert_at("abc", (0, 0), Orientation::Vert).unwrap();
+        let mut strip = Layout::new();
+        strip
+            .insert_at("abcdefghi", (0, 0), Orientation::Horiz)
+            .unwrap();
+
+        // The same area, but the square is better.
+        assert_eq!(Area.compare(&square, &strip), cmp::Ordering::Equal);
+        let scorer = AspectRatio(ASPECT_RATIO_SQUARE);
+        assert_eq!(scorer.compare(&square, &strip), cmp::Ordering::Greater);
+        assert_eq!(scorer.compare(&strip, &square), cmp::Ordering::Less);
+        let scorer = FittedArea(ASPECT_RATIO_SQUARE);
+        assert_eq!(scorer.compare(&square, &strip), cmp::Ordering::Greater);
+
+        // A wide strip fits better on a landscape sheet.
+        let scorer = AspectRatio(9.0);
+        assert_eq!(scorer.compare(&square, &strip), cmp::Ordering::Less);
+    }
+
+    #[test]
+    fn test_density() {
+        let mut cross = Layout::new();
+        cross.insert_at("abc", (0, 0), Orientation::Horiz).unwrap();
+        cross.insert_at("abc", (0, 0), Orientation::Vert).unwrap();
+        let mut frame = cross.clone();
+        frame.insert_at("cxa", (0, 2), Orientation::Horiz).unwrap();
+        frame.insert_at("cya", (2, 0), Orientation::Vert).unwrap();
+        assert_eq!(Density.compare(&frame, &cross), cmp::Ordering::Greater);
+        assert_eq!(Density.compare(&cross, &cross), cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn test_parse_scorer() {
+        let mut layout = Layout::new();
+        layout.insert_at("abc", (0, 0), Orientation::Horiz).unwrap();
+        layout.insert_at("abc", (0, 0), Orientation::Vert).unwrap();
+
+        let scorer = parse_scorer("4*crossings+area").unwrap();
+        assert_eq!(scorer.score(&layout), 4.0 - 9.0);
+        assert_eq!(scorer.recipe(), "4*crossings+1*area");
+
+        let scorer = parse_scorer("crossings, fitted_area(0.5)").unwrap();
+        assert_eq!(scorer.recipe(), "crossings,fitted_area(0.5)");
+        let reparsed = parse_scorer(&scorer.recipe()).unwrap();
+        assert_eq!(reparsed.recipe(), scorer.recipe());
+
+        assert!(parse_scorer("crossings,squareness").is_err());
+        assert!(parse_scorer("x*area").is_err());
+        assert!(parse_scorer("aspect_ratio(1").is_err());
+        for recipe in [
+            "aspect_ratio(0)",
+            "aspect_ratio(-1)",
+            "fitted_area(0)",
+            "crossings,fitted_area(inf)",
+        ] {
+            assert!(parse_scorer(recipe).is_err(), "{recipe}");
+        }
+        assert!(scorer_by_name("aspect_ratio", Some(0.0)).is_none());
+    }
+}
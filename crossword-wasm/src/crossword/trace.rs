@@ -0,0 +1,252 @@
+use std::fmt;
+use std::str::FromStr;
+
+use wasm_bindgen::prelude::*;
+
+use super::{Layout, LayoutError, Orientation, WordPosition, XY};
+
+/// A record of how a layout was generated, see [crate::CrosswordGenerator::with_trace]. Replaying the
+/// placements one by one gives the layout at each step.
+///
+/// The trace is written as text, one line per entry, and can be parsed back:
+///
+/// ```text
+/// initial burak 0 0 Hor
+/// candidate kark 4 0 Ver first
+/// candidate kark 0 -1 Hor worse
+/// place kark 4 0 Ver
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trace {
+    /// The words on the layout before the first step, e.g. the pinned words.
+    pub initial: Vec<WordPosition>,
+    pub steps: Vec<TraceStep>,
+}
+
+/// One word put on the layout.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceStep {
+    /// The positions considered at this step, in the order they were compared. Empty in the modes that do not
+    /// build the layout word by word, like [crate::GeneratorMode::Beam] and
+    /// [crate::GeneratorMode::Exhaustive].
+    pub candidates: Vec<TraceCandidate>,
+    pub placement: WordPosition,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceCandidate {
+    pub word: String,
+    pub pos: XY,
+    pub orientation: Orientation,
+    /// How the layout with this word compared to the best layout of the step so far.
+    pub verdict: Verdict,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Verdict {
+    /// The first candidate of the step, there was nothing to compare with.
+    First,
+    Better,
+    /// As good as the best one. Ties are kept or replaced by the tie breaker.
+    Equal,
+    Worse,
+}
+
+impl Trace {
+    /// The layout after the first `step` steps. 0 is the initial layout, [Trace::steps] length is the final
+    /// layout. Fails if the placements conflict, which happens only with a trace that was not recorded by the
+    /// generator, e.g. one parsed from edited text.
+    pub fn layout_at(&self, step: usize) -> Result<Layout, LayoutError> {
+        let mut layout = Layout::new();
+        let placements = self.steps.iter().take(step).map(|s| &s.placement);
+        for wp in self.initial.iter().chain(placements) {
+            layout.insert_at(&wp.word, wp.pos, wp.orientation)?;
+        }
+        Ok(layout.normalize())
+    }
+
+    /// Record the placements of a finished layout, without the candidates. The first `n_initial` words are
+    /// the initial words.
+    pub(crate) fn from_layout(layout: &Layout, n_initial: usize) -> Trace {
+        let words = layout.get_word_positions();
+        Trace {
+            initial: words[..n_initial].to_vec(),
+            steps: words[n_initial..]
+                .iter()
+                .map(|wp| TraceStep {
+                    candidates: Vec::new(),
+                    placement: wp.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::First => write!(f, "first"),
+            Verdict::Better => write!(f, "better"),
+            Verdict::Equal => write!(f, "equal"),
+            Verdict::Worse => write!(f, "worse"),
+        }
+    }
+}
+
+impl FromStr for Verdict {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(Verdict::First),
+            "better" => Ok(Verdict::Better),
+            "equal" => Ok(Verdict::Equal),
+            "worse" => Ok(Verdict::Worse),
+            _ => Err(format!("bad verdict: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_wp = |f: &mut fmt::Formatter<'_>, wp: &WordPosition| {
+            write!(
+                f,
+                "{} {} {} {}",
+                wp.word, wp.pos.x, wp.pos.y, wp.orientation
+            )
+        };
+        for wp in &self.initial {
+            write!(f, "initial ")?;
+            write_wp(f, wp)?;
+            writeln!(f)?;
+        }
+        for step in &self.steps {
+            for c in &step.candidates {
+                writeln!(
+                    f,
+                    "candidate {} {} {} {} {}",
+                    c.word, c.pos.x, c.pos.y, c.orientation, c.verdict
+                )?;
+            }
+            write!(f, "place ")?;
+            write_wp(f, &step.placement)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Trace {
+    type Err = String;
+
+    /// Parse the text written by [Trace]'s Display.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_wp = |fields: &[&str]| -> Result<WordPosition, String> {
+            let number = |s: &str| s.parse::<i32>().map_err(|_| format!("not a number: {s}"));
+            match fields {
+                [word, x, y, orientation] => Ok(WordPosition {
+                    word: word.to_string(),
+                    pos: (number(x)?, number(y)?).into(),
+                    orientation: orientation.parse()?,
+                }),
+                _ => Err(format!("bad trace fields: {}", fields.join(" "))),
+            }
+        };
+        let mut trace = Trace::default();
+        let mut candidates = Vec::new();
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                ["initial", ref rest @ ..] => trace.initial.push(parse_wp(rest)?),
+                ["candidate", ref rest @ .., verdict] => {
+                    let wp = parse_wp(rest)?;
+                    candidates.push(TraceCandidate {
+                        word: wp.word,
+                        pos: wp.pos,
+                        orientation: wp.orientation,
+                        verdict: verdict.parse()?,
+                    });
+                }
+                ["place", ref rest @ ..] => trace.steps.push(TraceStep {
+                    candidates: std::mem::take(&mut candidates),
+                    placement: parse_wp(rest)?,
+                }),
+                _ => return Err(format!("bad trace line: {line}")),
+            }
+        }
+        if !candidates.is_empty() {
+            return Err("trace ends with candidates but no placement".to_owned());
+        }
+        Ok(trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LayoutError, Trace, Verdict};
+    use crate::crossword::generator::tests::POLISH_WORDS;
+    use crate::{CrosswordGenerator, GeneratorMode};
+
+    #[test]
+    fn test_replay() {
+        let words = POLISH_WORDS;
+        let report = CrosswordGenerator::for_mode(GeneratorMode::Automatic)
+            .with_trace(true)
+            .generate_crossword(&words, GeneratorMode::Automatic);
+        let trace = report.trace.unwrap();
+        assert_eq!(trace.steps.len(), words.len());
+        assert!(trace.layout_at(0).unwrap().get_word_positions().is_empty());
+        assert_eq!(trace.layout_at(1).unwrap().get_word_positions().len(), 1);
+        assert_eq!(
+            format!("{}", trace.layout_at(trace.steps.len()).unwrap()),
+            format!("{}", report.layout)
+        );
+        let first = &trace.steps[0];
+        assert_eq!(first.candidates[0].verdict, Verdict::First);
+        assert!(
+            first
+                .candidates
+                .iter()
+                .any(|c| c.word == first.placement.word
+                    && c.pos == first.placement.pos
+                    && c.orientation == first.placement.orientation)
+        );
+
+        let parsed: Trace = trace.to_string().parse().unwrap();
+        assert_eq!(parsed, trace);
+    }
+
+    #[test]
+    fn test_replay_beam() {
+        let words = POLISH_WORDS;
+        let report = CrosswordGenerator::for_mode(GeneratorMode::Beam)
+            .with_trace(true)
+            .generate_crossword(&words, GeneratorMode::Beam);
+        let trace = report.trace.unwrap();
+        assert!(trace.steps.iter().all(|s| s.candidates.is_empty()));
+        assert_eq!(
+            format!("{}", trace.layout_at(trace.steps.len()).unwrap()),
+            format!("{}", report.layout)
+        );
+    }
+
+    #[test]
+    fn test_conflicting_trace() {
+        let trace: Trace = "initial abc 0 0 Hor\nplace xyz 2 0 Ver".parse().unwrap();
+        assert!(trace.layout_at(0).is_ok());
+        assert!(matches!(
+            trace.layout_at(1),
+            Err(LayoutError::ConflictingLetters { .. })
+        ));
+    }
+}
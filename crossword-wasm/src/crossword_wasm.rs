@@ -1,33 +1,963 @@
 // https://rustwasm.github.io/docs/wasm-bindgen/reference/types/boxed-slices.html
 
-use crate::{GeneratorMode, Orientation, crossword};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::{
+    Annealer, CancellationToken, CrosswordGenerator, GenerationReport, GenerationSteps,
+    GeneratorMode, Layout, LayoutError, Orientation, PinError, Progress, Puzzle, PuzzleMetadata,
+    Scorer, SearchBudget, SizeLimit, Trace, UnplacedReason, UnplacedWord, Verdict, WordPosition,
+    XY, fitted_compactness_scorer, format_log_record, parse_scorer,
+};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
 pub struct Word {
     pub word: String,
     pub id: usize,
     pub x: i32,
     pub y: i32,
-    pub orientation: String,
+    pub orientation: Orientation,
+    /// Set only by [WasmCrossword], the other APIs know no clues.
+    pub clue: Option<String>,
+}
+
+/// Optional settings of the generator. Unset fields use the defaults.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct GeneratorOptions {
+    /// How many partial layouts to keep in "Beam" mode.
+    pub beam_width: Option<usize>,
+    /// How many partial layouts can "Exhaustive" mode visit.
+    pub max_nodes: Option<usize>,
+    /// How many milliseconds can "Exhaustive" mode take.
+    pub max_millis: Option<u32>,
+    /// If set, improve the generated layout with that many steps of simulated annealing. The moves are ranked
+    /// with the scoring callback of [generate_crossword_js] if there is one.
+    pub anneal_iterations: Option<usize>,
+    /// Break ties randomly. The same seed always gives the same layout.
+    pub seed: Option<u32>,
+    /// The layout is never wider than that. The words that do not fit are left out.
+    pub max_width: Option<u32>,
+    /// The layout is never higher than that. The words that do not fit are left out.
+    pub max_height: Option<u32>,
+    /// Prefer layouts with this width to height ratio, e.g. 1.0 for a square or 0.707 for A4 portrait.
+    pub aspect_ratio: Option<f64>,
+    /// Record how the crossword was built, see [GeneratedCrossword::trace]. The trace does not include the
+    /// annealing.
+    pub record_trace: bool,
+    pins: Vec<WordPosition>,
+    scorer: Option<String>,
+    cancellation: Option<CancellationToken>,
+    on_progress: Option<js_sys::Function>,
 }
 
 #[wasm_bindgen]
-pub fn generate_crossword_js(words: Box<[String]>, mode: String) -> Result<Vec<Word>, JsValue> {
-    let mode = match mode.as_str() {
-        "Automatic" => GeneratorMode::Automatic,
-        "InputOrder" => GeneratorMode::InputOrder,
-        _ => return Err(JsValue::from_str("bad generator mode")),
+impl GeneratorOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GeneratorOptions {
+        GeneratorOptions::default()
+    }
+
+    /// Pin the word at the position and orientation. The other words are placed around the pinned words. The
+    /// pinned words keep their positions relative to each other, the crossword starts at 0, 0.
+    pub fn pin(&mut self, word: String, x: i32, y: i32, orientation: Orientation) {
+        self.pins.push(WordPosition {
+            word,
+            pos: (x, y).into(),
+            orientation,
+        });
+    }
+
+    /// Rank the layouts with the scoring recipe, e.g. "crossings,area" or "4*crossings+area". The recipe is
+    /// also used by the annealing.
+    pub fn set_scorer(&mut self, recipe: String) -> Result<(), JsValue> {
+        parse_scorer(&recipe).map_err(|e| JsValue::from_str(&e))?;
+        self.scorer = Some(recipe);
+        Ok(())
+    }
+
+    /// Stop the generation when the token is cancelled, and return the best crossword found until then.
+    pub fn set_cancellation(&mut self, token: &WasmCancellationToken) {
+        self.cancellation = Some(token.token.clone());
+    }
+
+    /// Call the function with a [GenerationProgress] as the generation goes, e.g. to post it from a Web Worker.
+    /// The function can cancel the generation with the token given to [GeneratorOptions::set_cancellation].
+    pub fn set_on_progress(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "(progress: GenerationProgress) => void")]
+        callback: js_sys::Function,
+    ) {
+        self.on_progress = Some(callback);
+    }
+}
+
+impl GeneratorOptions {
+    pub(crate) fn pins(&self) -> &[WordPosition] {
+        &self.pins
+    }
+
+    pub(crate) fn to_generator(&self, mode: GeneratorMode) -> CrosswordGenerator {
+        let generator = match self.aspect_ratio {
+            Some(target) => CrosswordGenerator::for_mode_with_aspect_ratio(mode, target),
+            None => CrosswordGenerator::for_mode(mode),
+        };
+        let mut generator = generator
+            .with_seed(self.seed.map(u64::from))
+            .with_size_limit(self.size_limit())
+            .with_trace(self.record_trace);
+        if let Some(recipe) = &self.scorer {
+            generator = generator.with_scorer(parse_scorer(recipe).unwrap());
+        }
+        if let Some(beam_width) = self.beam_width {
+            generator = generator.with_beam_width(beam_width);
+        }
+        if self.max_nodes.is_some() || self.max_millis.is_some() {
+            generator = generator.with_budget(SearchBudget {
+                max_nodes: self.max_nodes,
+                max_duration: self.max_millis.map(|ms| Duration::from_millis(ms.into())),
+            });
+        }
+        if let Some(token) = &self.cancellation {
+            generator = generator.with_cancellation(token.clone());
+        }
+        if let Some(callback) = &self.on_progress {
+            let callback = callback.clone();
+            generator = generator.with_progress(Box::new(move |progress| {
+                let progress = JsValue::from(GenerationProgress::from_progress(progress));
+                if let Err(err) = callback.call1(&JsValue::NULL, &progress) {
+                    log::warn!(err:?; "progress callback failed");
+                }
+            }));
+        }
+        generator
+    }
+}
+
+impl GeneratorOptions {
+    fn generate(
+        &self,
+        generator: &CrosswordGenerator,
+        words: &[&str],
+        mode: GeneratorMode,
+    ) -> Result<GenerationReport, JsValue> {
+        let mut report = generator
+            .generate_pinned_crossword(words, &self.pins, mode)
+            .map_err(|e| pin_error_to_js(&e))?;
+        if !report.cancelled {
+            report.layout = self.anneal(report.layout);
+        }
+        Ok(report)
+    }
+
+    fn size_limit(&self) -> SizeLimit {
+        SizeLimit {
+            max_width: self.max_width,
+            max_height: self.max_height,
+        }
+    }
+
+    pub(crate) fn anneal(&self, layout: Layout) -> Layout {
+        match self.annealer() {
+            Some(annealer) => annealer.optimize(&layout),
+            None => layout,
+        }
+    }
+
+    /// The annealer if [GeneratorOptions::anneal_iterations] is set.
+    fn annealer(&self) -> Option<Annealer> {
+        let iterations = self.anneal_iterations?;
+        let mut annealer = Annealer {
+            iterations,
+            seed: self.seed.unwrap_or_default().into(),
+            size_limit: self.size_limit(),
+            pinned: self.pins.iter().map(|pin| pin.word.clone()).collect(),
+            ..Annealer::default()
+        };
+        if let Some(target) = self.aspect_ratio {
+            annealer.scorer = fitted_compactness_scorer(target);
+        }
+        if let Some(recipe) = &self.scorer {
+            annealer.scorer = parse_scorer(recipe).unwrap();
+        }
+        Some(annealer)
+    }
+}
+
+/// Stops a running generation, see [GeneratorOptions::set_cancellation].
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmCancellationToken {
+    token: CancellationToken,
+}
+
+#[wasm_bindgen]
+impl WasmCancellationToken {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmCancellationToken {
+        WasmCancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+/// How far a generation got, see [Progress].
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct GenerationProgress {
+    pub words_placed: usize,
+    pub candidates_evaluated: usize,
+    pub best_score: f64,
+}
+
+impl GenerationProgress {
+    fn from_progress(progress: &Progress) -> GenerationProgress {
+        GenerationProgress {
+            words_placed: progress.words_placed,
+            candidates_evaluated: progress.candidates_evaluated,
+            best_score: progress.best_score,
+        }
+    }
+}
+
+/// A word that is not on the crossword, see [UnplacedWord].
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct DroppedWord {
+    pub word: String,
+    pub reason: UnplacedReason,
+    /// The reason for the user.
+    pub message: String,
+}
+
+impl DroppedWord {
+    pub(crate) fn from_unplaced(unplaced: &UnplacedWord) -> DroppedWord {
+        DroppedWord {
+            word: unplaced.word.clone(),
+            reason: unplaced.reason,
+            message: unplaced.reason.to_string(),
+        }
+    }
+}
+
+/// The crossword returned by [generate_crossword_js], and the words that did not make it.
+#[wasm_bindgen]
+pub struct GeneratedCrossword {
+    words: Vec<Word>,
+    dropped: Vec<DroppedWord>,
+    trace: Option<CrosswordTrace>,
+    cancelled: bool,
+}
+
+#[wasm_bindgen]
+impl GeneratedCrossword {
+    #[wasm_bindgen(getter)]
+    pub fn words(&self) -> Vec<Word> {
+        self.words.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn dropped(&self) -> Vec<DroppedWord> {
+        self.dropped.clone()
+    }
+
+    /// Set if [GeneratorOptions::record_trace] is.
+    #[wasm_bindgen(getter)]
+    pub fn trace(&self) -> Option<CrosswordTrace> {
+        self.trace.clone()
+    }
+
+    /// True if the generation was cancelled, see [GeneratorOptions::set_cancellation]. The words are the best
+    /// crossword found until then, without annealing.
+    #[wasm_bindgen(getter)]
+    pub fn cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+/// A position considered at a step of a [CrosswordTrace].
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct TraceCandidateJs {
+    pub word: String,
+    pub x: i32,
+    pub y: i32,
+    pub orientation: Orientation,
+    /// Compared to the best candidate of the step so far.
+    pub verdict: Verdict,
+}
+
+/// How a crossword was built, one placed word per step. See [Trace].
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct CrosswordTrace {
+    trace: Trace,
+}
+
+#[wasm_bindgen]
+impl CrosswordTrace {
+    /// Parse the text from [CrosswordTrace::to_text], e.g. one attached to a bug report.
+    pub fn from_text(text: String) -> Result<CrosswordTrace, JsValue> {
+        let trace = text.parse().map_err(|e: String| JsValue::from_str(&e))?;
+        Ok(CrosswordTrace { trace })
+    }
+
+    pub fn to_text(&self) -> String {
+        self.trace.to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn step_count(&self) -> usize {
+        self.trace.steps.len()
+    }
+
+    /// The words of the crossword after the first `step` steps. 0 gives the initial words, e.g. the pinned
+    /// ones, and [CrosswordTrace::step_count] gives the final crossword. Throws the LayoutError if the
+    /// placements conflict.
+    pub fn words_at(&self, step: usize) -> Result<Vec<Word>, JsValue> {
+        let layout = self
+            .trace
+            .layout_at(step)
+            .map_err(|e| layout_error_to_js(&e))?;
+        Ok(layout_to_words(&layout))
+    }
+
+    /// The positions considered at the step, starting from 0. Empty if the mode does not record them.
+    pub fn candidates_at(&self, step: usize) -> Vec<TraceCandidateJs> {
+        let Some(step) = self.trace.steps.get(step) else {
+            return Vec::new();
+        };
+        step.candidates
+            .iter()
+            .map(|c| TraceCandidateJs {
+                word: c.word.clone(),
+                x: c.pos.x,
+                y: c.pos.y,
+                orientation: c.orientation,
+                verdict: c.verdict,
+            })
+            .collect()
+    }
+}
+
+/// One of the layouts returned by [generate_crossword_alternatives_js], or a candidate layout passed to the
+/// scoring callback of [generate_crossword_js].
+#[wasm_bindgen]
+pub struct CrosswordLayout {
+    words: Vec<Word>,
+    density: f64,
+    crossings: usize,
+    width: u32,
+    height: u32,
+}
+
+impl CrosswordLayout {
+    fn from_layout(layout: &Layout) -> CrosswordLayout {
+        let dim = layout.dimensions();
+        CrosswordLayout {
+            words: layout_to_words(layout),
+            density: layout.density(),
+            crossings: layout.crossings_count(),
+            width: dim.x as u32,
+            height: dim.y as u32,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl CrosswordLayout {
+    #[wasm_bindgen(getter)]
+    pub fn words(&self) -> Vec<Word> {
+        self.words.clone()
+    }
+
+    /// Share of the fields with a letter, from 0.0 to 1.0.
+    #[wasm_bindgen(getter)]
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    /// How many fields are shared by two words.
+    #[wasm_bindgen(getter)]
+    pub fn crossings(&self) -> usize {
+        self.crossings
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+/// A layout packed into a few typed arrays, for when the words are passed often, e.g. to animate the
+/// generation. Reading [Word]s copies each string, this copies one buffer per field.
+///
+/// Word `i` is at `positions[4 * i]` (x), `positions[4 * i + 1]` (y), `positions[4 * i + 2]` (orientation, 0
+/// for horizontal and 1 for vertical) and `positions[4 * i + 3]` (id, see [Word]). Its letters are the UTF-8
+/// bytes of `text` from `word_ends[i - 1]` (or 0) to `word_ends[i]`, e.g.
+/// `new TextDecoder().decode(text.subarray(start, end))`.
+#[wasm_bindgen]
+pub struct PackedLayout {
+    positions: Vec<i32>,
+    text: Vec<u8>,
+    word_ends: Vec<u32>,
+}
+
+impl PackedLayout {
+    fn from_layout(layout: &Layout) -> PackedLayout {
+        let words = layout.get_words_with_ids();
+        let mut packed = PackedLayout {
+            positions: Vec::with_capacity(4 * words.len()),
+            text: Vec::new(),
+            word_ends: Vec::with_capacity(words.len()),
+        };
+        for (wp, id) in words {
+            let orientation = match wp.orientation {
+                Orientation::Horiz => 0,
+                Orientation::Vert => 1,
+            };
+            packed
+                .positions
+                .extend([wp.pos.x, wp.pos.y, orientation, id as i32]);
+            packed.text.extend_from_slice(wp.word.as_bytes());
+            packed.word_ends.push(packed.text.len() as u32);
+        }
+        packed
+    }
+}
+
+#[wasm_bindgen]
+impl PackedLayout {
+    #[wasm_bindgen(getter)]
+    pub fn word_count(&self) -> usize {
+        self.word_ends.len()
+    }
+
+    /// x, y, orientation and id of each word.
+    #[wasm_bindgen(getter)]
+    pub fn positions(&self) -> Vec<i32> {
+        self.positions.clone()
+    }
+
+    /// All the words one after another, as UTF-8.
+    #[wasm_bindgen(getter)]
+    pub fn text(&self) -> Vec<u8> {
+        self.text.clone()
+    }
+
+    /// Where each word ends in [PackedLayout::text], in bytes.
+    #[wasm_bindgen(getter)]
+    pub fn word_ends(&self) -> Vec<u32> {
+        self.word_ends.clone()
+    }
+}
+
+/// Scores the candidate layouts with a callback that can fail, e.g. a JS function, see [js_score]. The first
+/// error of the callback is kept in `error`, and from then on every layout gets the worst score, so the
+/// generation finishes quickly and the caller reports the error.
+struct CallbackScorer<F, E> {
+    callback: F,
+    error: Rc<RefCell<Option<E>>>,
+}
+
+impl<F, E> Scorer for CallbackScorer<F, E>
+where
+    F: Fn(&Layout) -> Result<f64, E>,
+{
+    fn score(&self, layout: &Layout) -> f64 {
+        if self.error.borrow().is_some() {
+            return f64::NEG_INFINITY;
+        }
+        match (self.callback)(layout) {
+            Ok(score) => score,
+            Err(err) => {
+                *self.error.borrow_mut() = Some(err);
+                f64::NEG_INFINITY
+            }
+        }
+    }
+
+    fn recipe(&self) -> String {
+        "js".to_owned()
+    }
+}
+
+/// Call the JS scoring function with a [CrosswordLayout]. It must return a number, larger is better.
+fn js_score(callback: &js_sys::Function, layout: &Layout) -> Result<f64, JsValue> {
+    let summary = JsValue::from(CrosswordLayout::from_layout(layout));
+    callback.call1(&JsValue::NULL, &summary).and_then(|score| {
+        score
+            .as_f64()
+            .filter(|score| !score.is_nan())
+            .ok_or_else(|| JsValue::from_str("scoring callback must return a number"))
+    })
+}
+
+/// Generate a crossword. If `score` is given, it ranks the candidate layouts instead of the built-in scorer. It
+/// is called with a [CrosswordLayout] and returns a number, larger is better. The function should depend only
+/// on the layout, so the same input gives the same crossword. If it throws, the error is returned. It is called
+/// in every mode, "Exhaustive" mode uses it to rank the layouts with the same number of words. The annealing,
+/// see [GeneratorOptions::anneal_iterations], uses it too.
+#[wasm_bindgen]
+pub fn generate_crossword_js(
+    words: Vec<String>,
+    mode: GeneratorMode,
+    options: Option<GeneratorOptions>,
+    #[wasm_bindgen(unchecked_param_type = "((layout: CrosswordLayout) => number) | undefined")]
+    score: Option<js_sys::Function>,
+) -> Result<GeneratedCrossword, JsValue> {
+    let options = options.unwrap_or_default();
+    let mut generator = options.to_generator(mode);
+    let score_error = Rc::new(RefCell::new(None));
+    let js_scorer = |callback: js_sys::Function| -> Box<dyn Scorer> {
+        Box::new(CallbackScorer {
+            callback: move |layout: &Layout| js_score(&callback, layout),
+            error: score_error.clone(),
+        })
     };
+    if let Some(callback) = &score {
+        generator = generator.with_scorer(js_scorer(callback.clone()));
+    }
 
     let words: Vec<&str> = words.iter().map(|s| s.as_ref()).collect();
-    let layout = crossword::generate_crossword(&words, mode);
-    let orientation_to_string = |o: Orientation| match o {
-        Orientation::Horiz => "hor".to_owned(),
-        Orientation::Vert => "ver".to_owned(),
-    };
+    let mut report = generator
+        .generate_pinned_crossword(&words, &options.pins, mode)
+        .map_err(|e| pin_error_to_js(&e))?;
+    if !report.cancelled
+        && let Some(mut annealer) = options.annealer()
+    {
+        if let Some(callback) = score {
+            annealer.scorer = js_scorer(callback);
+        }
+        report.layout = annealer.optimize(&report.layout);
+    }
+    if let Some(err) = score_error.take() {
+        return Err(err);
+    }
+    Ok(GeneratedCrossword {
+        words: layout_to_words(&report.layout),
+        dropped: report
+            .unplaced
+            .iter()
+            .map(DroppedWord::from_unplaced)
+            .collect(),
+        trace: report.trace.map(|trace| CrosswordTrace { trace }),
+        cancelled: report.cancelled,
+    })
+}
+
+/// Like [generate_crossword_js] without the scoring callback, but return the crossword as a [PackedLayout].
+#[wasm_bindgen]
+pub fn generate_crossword_packed_js(
+    words: Vec<String>,
+    mode: GeneratorMode,
+    options: Option<GeneratorOptions>,
+) -> Result<PackedLayout, JsValue> {
+    let options = options.unwrap_or_default();
+    let generator = options.to_generator(mode);
+
+    let words: Vec<&str> = words.iter().map(|s| s.as_ref()).collect();
+    let report = options.generate(&generator, &words, mode)?;
+    Ok(PackedLayout::from_layout(&report.layout))
+}
+
+/// Generate up to `count` different layouts, best first. See [CrosswordGenerator::generate_alternatives]. It
+/// takes a few generations per layout, in "Exhaustive" mode they share the search budget of a single one.
+#[wasm_bindgen]
+pub fn generate_crossword_alternatives_js(
+    words: Vec<String>,
+    mode: GeneratorMode,
+    count: usize,
+    min_distance: f64,
+    options: Option<GeneratorOptions>,
+) -> Result<Vec<CrosswordLayout>, JsValue> {
+    let options = options.unwrap_or_default();
+    let generator = options.to_generator(mode);
+
+    let words: Vec<&str> = words.iter().map(|s| s.as_ref()).collect();
+    let alternatives = generator
+        .generate_alternatives(&words, &options.pins, mode, count, min_distance)
+        .map_err(|e| pin_error_to_js(&e))?;
+    Ok(alternatives
+        .into_iter()
+        .map(|layout| CrosswordLayout::from_layout(&options.anneal(layout)))
+        .collect())
+}
+
+/// Generates a crossword one word per call, see [GenerationSteps]. Call [CrosswordSteps::step] e.g. once per
+/// animation frame, so that a long list of words does not freeze the page. Unlike [generate_crossword_js],
+/// the layout is not annealed.
+#[wasm_bindgen]
+pub struct CrosswordSteps {
+    steps: GenerationSteps,
+}
+
+#[wasm_bindgen]
+impl CrosswordSteps {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        words: Vec<String>,
+        mode: GeneratorMode,
+        options: Option<GeneratorOptions>,
+    ) -> Result<CrosswordSteps, JsValue> {
+        let options = options.unwrap_or_default();
+        let generator = options.to_generator(mode);
+        let words: Vec<&str> = words.iter().map(|s| s.as_ref()).collect();
+        let steps = generator
+            .into_pinned_steps(&words, &options.pins, mode)
+            .map_err(|e| pin_error_to_js(&e))?;
+        Ok(CrosswordSteps { steps })
+    }
+
+    /// Place the next word. Return the words of the crossword so far, or undefined when there is nothing more
+    /// to place.
+    pub fn step(&mut self) -> Option<Vec<Word>> {
+        self.steps.next().map(|layout| layout_to_words(&layout))
+    }
+
+    /// Like [CrosswordSteps::step], but return the crossword as a [PackedLayout].
+    pub fn step_packed(&mut self) -> Option<PackedLayout> {
+        self.steps
+            .next()
+            .map(|layout| PackedLayout::from_layout(&layout))
+    }
+
+    /// The words of the crossword so far.
+    pub fn words(&self) -> Vec<Word> {
+        layout_to_words(&self.steps.layout())
+    }
+
+    /// The words that are not on the crossword so far, and why. Before the last step some of them are just
+    /// "NotReached".
+    pub fn dropped(&self) -> Vec<DroppedWord> {
+        self.steps
+            .report()
+            .unplaced
+            .iter()
+            .map(DroppedWord::from_unplaced)
+            .collect()
+    }
+}
+
+/// A crossword that is built word by word. Adding a word keeps the words that are already placed where they
+/// are, unless the new word does not fit anywhere. Then the whole layout is generated again.
+///
+/// The crossword stays on the Rust side between the edits, so the page passes only the edits and reads only
+/// what it shows. It is kept as a [Puzzle], so the clues and the metadata go along with the words.
+#[wasm_bindgen]
+pub struct WasmCrossword {
+    puzzle: Puzzle,
+    /// All the words given so far, also the ones that are not on the layout.
+    input_words: Vec<String>,
+    mode: GeneratorMode,
+    options: GeneratorOptions,
+}
+
+#[wasm_bindgen]
+impl WasmCrossword {
+    #[wasm_bindgen(constructor)]
+    pub fn new(mode: GeneratorMode, options: Option<GeneratorOptions>) -> WasmCrossword {
+        WasmCrossword {
+            puzzle: Puzzle::default(),
+            input_words: Vec::new(),
+            mode,
+            options: options.unwrap_or_default(),
+        }
+    }
+
+    /// Load a crossword from the words text of [WasmCrossword::export_words], with its clues and metadata.
+    /// The words with a position are pinned there, and the other words are placed around them.
+    pub fn from_words_text(
+        text: &str,
+        mode: GeneratorMode,
+        options: Option<GeneratorOptions>,
+    ) -> Result<WasmCrossword, JsValue> {
+        let (puzzle, free_words) =
+            Puzzle::from_words_text(text).map_err(|err| JsValue::from(js_sys::Error::new(&err)))?;
+        let mut options = options.unwrap_or_default();
+        options
+            .pins
+            .extend(puzzle.layout.get_word_positions().iter().cloned());
+        let mut input_words: Vec<String> = puzzle
+            .layout
+            .words()
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        input_words.extend(free_words);
+        let mut crossword = WasmCrossword {
+            puzzle,
+            input_words,
+            mode,
+            options,
+        };
+        crossword.regenerate()?;
+        Ok(crossword)
+    }
+
+    /// Replace the layout with one generated from scratch.
+    pub fn generate(&mut self, words: Vec<String>) -> Result<(), JsValue> {
+        self.input_words = words;
+        self.regenerate()
+    }
+
+    /// Generate the layout again from all the words given so far, e.g. after removing words left gaps. The
+    /// pinned words and the words placed by hand stay where they are relative to each other.
+    pub fn regenerate(&mut self) -> Result<(), JsValue> {
+        self.update_pins();
+        let generator = self.options.to_generator(self.mode);
+        let word_refs: Vec<&str> = self.input_words.iter().map(|s| s.as_ref()).collect();
+        self.puzzle.layout = self
+            .options
+            .generate(&generator, &word_refs, self.mode)?
+            .layout;
+        Ok(())
+    }
+
+    /// Add a word. Return true if the other words stayed in place, false if the word did not fit and the
+    /// layout was generated again, see [WasmCrossword::regenerate].
+    pub fn add_word(&mut self, word: String) -> Result<bool, JsValue> {
+        let generator = self.options.to_generator(self.mode);
+        let layout = &self.puzzle.layout;
+        let extended = if layout.words().contains(&word.as_str()) {
+            Some(layout.clone())
+        } else {
+            generator.insert_word(layout, &word)
+        };
+        self.input_words.push(word);
+        match extended {
+            Some(layout) => {
+                self.puzzle.layout = layout;
+                Ok(true)
+            }
+            None => {
+                self.regenerate()?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Remove the word with its clue and its pin, and keep the other words in place. Return false if there is no
+    /// such word. The remaining words may not be connected anymore, see [WasmCrossword::validate].
+    pub fn remove_word(&mut self, word: String) -> bool {
+        let Some(i) = self.input_words.iter().position(|w| *w == word) else {
+            return false;
+        };
+        self.input_words.remove(i);
+        if let Some(i) = self.puzzle.layout.words().iter().position(|w| *w == word) {
+            self.puzzle.layout.remove_word(i);
+            self.puzzle.layout = std::mem::take(&mut self.puzzle.layout).normalize();
+        }
+        // The same word can be given more than once, the other copy keeps the clue and the pin.
+        let is_left =
+            self.input_words.contains(&word) || self.puzzle.layout.words().contains(&word.as_str());
+        if !is_left {
+            self.options.pins.retain(|pin| pin.word != word);
+            self.puzzle.set_clue(&word, "");
+        }
+        true
+    }
+
+    /// Put the word at the position and orientation by hand. The word is placed even if it
+    /// conflicts with the other words, see [WasmCrossword::validate]. Throws a LayoutError only if the word
+    /// cannot be put on any crossword, e.g. it has spaces. The word is pinned, so it stays in place when the
+    /// layout is generated again.
+    pub fn place_word(
+        &mut self,
+        word: String,
+        x: i32,
+        y: i32,
+        orientation: Orientation,
+    ) -> Result<(), JsValue> {
+        match self.puzzle.layout.insert_at(&word, (x, y), orientation) {
+            Ok(()) | Err(LayoutError::ConflictingLetters { .. }) => (),
+            Err(err) => return Err(layout_error_to_js(&err).into()),
+        }
+        self.puzzle.layout = std::mem::take(&mut self.puzzle.layout).normalize();
+        self.options.pins.retain(|pin| pin.word != word);
+        self.options.pins.push(WordPosition {
+            word: word.clone(),
+            pos: (x, y).into(),
+            orientation,
+        });
+        self.input_words.push(word);
+        Ok(())
+    }
+
+    /// The letter at the field, or undefined if the field is empty. The top left field is 0, 0.
+    pub fn cell(&self, x: i32, y: i32) -> Option<char> {
+        self.puzzle.layout.char_at((x, y))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.puzzle.layout.dimensions().x as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.puzzle.layout.dimensions().y as u32
+    }
+
+    /// What is wrong with the crossword, as LayoutErrors. The errors of a word have the `word` set, the
+    /// "ExceedsSizeLimit" error is about the whole crossword and has no `word`. Empty if the crossword is fine.
+    #[wasm_bindgen(unchecked_return_type = "Error[]")]
+    pub fn validate(&self) -> Vec<JsValue> {
+        let mut errors: Vec<JsValue> = self
+            .puzzle
+            .layout
+            .validate()
+            .iter()
+            .map(|(i, err)| {
+                let js_err = layout_error_to_js(err);
+                let word = &self.puzzle.layout.get_word_positions()[*i].word;
+                let _ = js_sys::Reflect::set(&js_err, &"word".into(), &word.into());
+                js_err.into()
+            })
+            .collect();
+        if !self
+            .options
+            .size_limit()
+            .allows(self.puzzle.layout.dimensions())
+        {
+            errors.push(layout_error_to_js(&LayoutError::ExceedsSizeLimit).into());
+        }
+        errors
+    }
+
+    /// The clue of the word, if it has one.
+    pub fn clue(&self, word: &str) -> Option<String> {
+        self.puzzle.clue(word).map(|c| c.to_owned())
+    }
+
+    /// Set the clue of the word, an empty clue removes it. The word does not have to be on the crossword.
+    pub fn set_clue(&mut self, word: &str, clue: &str) {
+        self.puzzle.set_clue(word, clue);
+    }
 
-    let cwords = layout
+    #[wasm_bindgen(getter)]
+    pub fn metadata(&self) -> PuzzleMetadata {
+        self.puzzle.metadata.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_metadata(&mut self, metadata: PuzzleMetadata) {
+        self.puzzle.metadata = metadata;
+    }
+
+    /// The grid as text, one line per row, with the empty fields filled with `fill`, e.g. "." or " ".
+    pub fn export_grid(&self, fill: char) -> String {
+        let mut grid = String::new();
+        for y in 0..self.height() as i32 {
+            let row: String = (0..self.width() as i32)
+                .map(|x| self.puzzle.layout.char_at((x, y)).unwrap_or(fill))
+                .collect();
+            grid.push_str(&row);
+            grid.push('\n');
+        }
+        grid
+    }
+
+    /// The puzzle as words text, one "word x y hor|ver clue" per line, see [Puzzle]. The command line
+    /// generator reads it back with the words pinned.
+    pub fn export_words(&self) -> String {
+        self.puzzle.to_words_text()
+    }
+
+    /// The words given to [WasmCrossword::generate] and [WasmCrossword::add_word] that are not on the
+    /// crossword, and why.
+    pub fn dropped(&self) -> Vec<DroppedWord> {
+        let generator = self.options.to_generator(self.mode);
+        let words: Vec<&str> = self.input_words.iter().map(|s| s.as_ref()).collect();
+        generator
+            .report(self.puzzle.layout.clone(), &words)
+            .unplaced
+            .iter()
+            .map(DroppedWord::from_unplaced)
+            .collect()
+    }
+
+    /// The words with their clues.
+    pub fn words(&self) -> Vec<Word> {
+        let mut words = layout_to_words(&self.puzzle.layout);
+        for word in &mut words {
+            word.clue = self.clue(&word.word);
+        }
+        words
+    }
+
+    /// The words as a [PackedLayout].
+    pub fn packed(&self) -> PackedLayout {
+        PackedLayout::from_layout(&self.puzzle.layout)
+    }
+
+    /// Share of the fields with a letter, from 0.0 to 1.0.
+    pub fn density(&self) -> f64 {
+        self.puzzle.layout.density()
+    }
+}
+
+impl WasmCrossword {
+    /// Move the pins of the words that are on the layout to where the words are now. The layout is normalized
+    /// after each edit, so the pins given earlier would not match the pins of the words placed later.
+    fn update_pins(&mut self) {
+        for pin in &mut self.options.pins {
+            let words = self.puzzle.layout.get_word_positions();
+            if let Some(wp) = words.iter().find(|wp| wp.word == pin.word) {
+                *pin = wp.clone();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[wasm_bindgen]
+impl WasmCrossword {
+    /// Load a crossword saved with [WasmCrossword::to_json]. The words of the puzzle stay where they are
+    /// relative to each other.
+    pub fn from_json(
+        json: &str,
+        mode: GeneratorMode,
+        options: Option<GeneratorOptions>,
+    ) -> Result<WasmCrossword, JsValue> {
+        let mut puzzle = crate::puzzle_from_json(json)
+            .map_err(|err| JsValue::from(js_sys::Error::new(&err.to_string())))?;
+        // The grid starts at 0, 0, see [WasmCrossword::cell].
+        puzzle.layout = std::mem::take(&mut puzzle.layout).normalize();
+        let input_words = puzzle
+            .layout
+            .words()
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        Ok(WasmCrossword {
+            puzzle,
+            input_words,
+            mode,
+            options: options.unwrap_or_default(),
+        })
+    }
+
+    /// The crossword as puzzle JSON, the same as the command line writes with `--output json`.
+    pub fn to_json(&self) -> String {
+        crate::puzzle_to_json(&self.puzzle)
+    }
+}
+
+pub(crate) fn layout_to_words(layout: &Layout) -> Vec<Word> {
+    layout
         .get_words_with_ids()
         .iter()
         .map(|(wp, id)| Word {
@@ -35,8 +965,213 @@ pub fn generate_crossword_js(words: Box<[String]>, mode: String) -> Result<Vec<W
             id: *id,
             x: wp.pos.x,
             y: wp.pos.y,
-            orientation: orientation_to_string(wp.orientation),
+            orientation: wp.orientation,
+            clue: None,
         })
-        .collect();
-    Ok(cwords)
+        .collect()
+}
+
+/// Make a JS `Error` named "LayoutError" with the details of the error as properties, so the UI can tell why
+/// the word cannot be placed: `kind` is the name of the variant, and `x`, `y`, `existing`, `new`, `word`
+/// and `chars` are set when the variant has them.
+fn layout_error_to_js(err: &LayoutError) -> js_sys::Error {
+    let js_err = js_sys::Error::new(&err.to_string());
+    js_err.set_name("LayoutError");
+    let set = |key: &str, value: JsValue| {
+        let _ = js_sys::Reflect::set(&js_err, &JsValue::from_str(key), &value);
+    };
+    let set_pos = |pos: &XY| {
+        set("x", pos.x.into());
+        set("y", pos.y.into());
+    };
+    let kind = match err {
+        LayoutError::ConflictingLetters { pos, existing, new } => {
+            set_pos(pos);
+            set("existing", existing.to_string().into());
+            set("new", new.to_string().into());
+            "ConflictingLetters"
+        }
+        LayoutError::EnvelopeOverlap { pos } => {
+            set_pos(pos);
+            "EnvelopeOverlap"
+        }
+        LayoutError::ExceedsSizeLimit => "ExceedsSizeLimit",
+        LayoutError::Disconnected => "Disconnected",
+        LayoutError::EmptyWord => "EmptyWord",
+        LayoutError::InvalidChars { word, chars } => {
+            set("word", word.into());
+            set("chars", chars.iter().collect::<String>().into());
+            "InvalidChars"
+        }
+    };
+    set("kind", kind.into());
+    js_err
+}
+
+/// Like [layout_error_to_js], with the pinned word in `pin`.
+fn pin_error_to_js(err: &PinError) -> JsValue {
+    let js_err = layout_error_to_js(&err.error);
+    js_err.set_message(&err.to_string());
+    let _ = js_sys::Reflect::set(
+        &js_err,
+        &JsValue::from_str("pin"),
+        &err.pin.word.as_str().into(),
+    );
+    js_err.into()
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(s: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = info)]
+    fn console_info(s: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = warn)]
+    fn console_warn(s: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(s: &str);
+}
+
+/// Sends the log records to the browser console.
+struct ConsoleLogger;
+
+static CONSOLE_LOGGER: ConsoleLogger = ConsoleLogger;
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format_log_record(record);
+        match record.level() {
+            log::Level::Error => console_error(&line),
+            log::Level::Warn => console_warn(&line),
+            log::Level::Info => console_info(&line),
+            log::Level::Debug | log::Level::Trace => console_debug(&line),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Log to the browser console at the level: "off" (the default), "error", "warn", "info", "debug" or
+/// "trace". "info" logs a summary of each generation, "trace" logs every candidate position, which is slow.
+#[wasm_bindgen]
+pub fn set_log_level_js(level: String) -> Result<(), JsValue> {
+    let level: log::LevelFilter = level
+        .parse()
+        .map_err(|_| JsValue::from_str(&format!("bad log level: {level}")))?;
+    // Fails if the logger is already set, which is fine.
+    let _ = log::set_logger(&CONSOLE_LOGGER);
+    log::set_max_level(level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use super::{CallbackScorer, WasmCrossword};
+    use crate::crossword::generator::tests::POLISH_WORDS;
+    use crate::{CrosswordGenerator, GeneratorMode, Layout, Orientation};
+
+    #[test]
+    fn test_callback_scorer_error() {
+        let words = POLISH_WORDS;
+        for mode in [GeneratorMode::Automatic, GeneratorMode::Exhaustive] {
+            let calls = Rc::new(Cell::new(0));
+            let error = Rc::new(RefCell::new(None));
+            let scorer = CallbackScorer {
+                callback: {
+                    let calls = calls.clone();
+                    move |_: &Layout| {
+                        calls.set(calls.get() + 1);
+                        Err::<f64, _>("bad score".to_owned())
+                    }
+                },
+                error: error.clone(),
+            };
+            CrosswordGenerator::new(Box::new(scorer)).generate_crossword(&words, mode);
+            assert_eq!(error.take().as_deref(), Some("bad score"));
+            // Not called again after the first error.
+            assert_eq!(calls.get(), 1);
+        }
+    }
+
+    #[test]
+    fn test_removed_word_is_not_regenerated() {
+        let text = "burak 0 0 hor\nkark 4 0 ver\nbacz\n";
+        let mut crossword =
+            WasmCrossword::from_words_text(text, GeneratorMode::Automatic, None).unwrap();
+        assert_eq!(crossword.puzzle.layout.words().len(), 3);
+        assert!(crossword.remove_word("kark".to_owned()));
+        crossword.regenerate().unwrap();
+        let words = crossword.puzzle.layout.words();
+        assert!(!words.contains(&"kark"));
+        assert!(words.contains(&"burak"));
+    }
+
+    #[test]
+    fn test_add_word_that_does_not_fit() {
+        let mut crossword = WasmCrossword::new(GeneratorMode::InputOrder, None);
+        crossword.generate(vec!["xyz".to_owned()]).unwrap();
+        crossword
+            .place_word("burak".to_owned(), 0, 5, Orientation::Horiz)
+            .unwrap();
+        crossword
+            .place_word("kark".to_owned(), 4, 5, Orientation::Vert)
+            .unwrap();
+        let offset = |crossword: &WasmCrossword| {
+            let words = crossword.puzzle.layout.get_word_positions();
+            let find = |word| words.iter().find(|wp| wp.word == word).unwrap().clone();
+            (
+                find("kark").pos - find("burak").pos,
+                find("kark").orientation,
+            )
+        };
+        let before = offset(&crossword);
+        crossword.set_clue("kark", "The back of the neck");
+
+        // It does not fit, so the layout is generated again from all the words, with the words placed by hand
+        // where they were.
+        assert!(!crossword.add_word("fog".to_owned()).unwrap());
+        assert_eq!(crossword.input_words, vec!["xyz", "burak", "kark", "fog"]);
+        assert_eq!(offset(&crossword), before);
+        let dropped: Vec<String> = crossword.dropped().into_iter().map(|d| d.word).collect();
+        assert_eq!(dropped, vec!["xyz", "fog"]);
+
+        // Removing one copy of a word keeps the clue of the other.
+        crossword.add_word("kark".to_owned()).unwrap();
+        assert!(crossword.remove_word("kark".to_owned()));
+        assert_eq!(
+            crossword.clue("kark").as_deref(),
+            Some("The back of the neck")
+        );
+        assert!(crossword.remove_word("kark".to_owned()));
+        assert_eq!(crossword.clue("kark"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_with_offset_layout() {
+        let json = r#"{
+            "version": 2,
+            "layout": [
+                { "word": "burak", "pos": { "x": 5, "y": 5 }, "orientation": "hor" },
+                { "word": "kark", "pos": { "x": 9, "y": 5 }, "orientation": "ver" }
+            ]
+        }"#;
+        let crossword = WasmCrossword::from_json(json, GeneratorMode::InputOrder, None).unwrap();
+        let grid = "burak\n....a\n....r\n....k\n";
+        assert_eq!(crossword.export_grid('.'), grid);
+        assert_eq!(crossword.cell(0, 0), Some('b'));
+        let read = WasmCrossword::from_json(&crossword.to_json(), GeneratorMode::InputOrder, None)
+            .unwrap();
+        assert_eq!(read.export_grid('.'), grid);
+    }
 }
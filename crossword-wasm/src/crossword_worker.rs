@@ -0,0 +1,298 @@
+//! Generation in a Web Worker, so that long generations do not freeze the page. The page posts
+//! `WorkerRequest` messages to the worker, and the worker posts back `WorkerResponse` messages, see the
+//! TypeScript definitions below. The messages are plain objects, so they pass through `postMessage`.
+//!
+//! The worker script only forwards the messages to [CrosswordWorker] and calls [CrosswordWorker::run] in
+//! short slices, so that it reads a "cancel" message while a generation runs:
+//!
+//! ```js
+//! const worker = init().then(() => new CrosswordWorker((message) => postMessage(message)))
+//! const run = (w) => { if (w.run(20)) setTimeout(() => run(w), 0) }
+//! onmessage = async (event) => { const w = await worker; w.on_message(event.data); run(w) }
+//! ```
+//!
+//! "Beam" and "Exhaustive" modes search the whole layout in one slice, for them set `max_millis` instead.
+
+use std::time::Duration;
+
+use wasm_bindgen::convert::TryFromJsValue;
+use wasm_bindgen::prelude::*;
+use web_time::Instant;
+
+use crate::crossword_wasm::{DroppedWord, GeneratorOptions, Word, layout_to_words};
+use crate::{CancellationToken, GenerationSteps, GeneratorMode, Orientation};
+
+#[wasm_bindgen(typescript_custom_section)]
+const WORKER_MESSAGES_TS: &str = r#"
+/** Posted to the crossword worker. A "generate" replaces the running generation. */
+export type WorkerRequest =
+  | { type: "generate"; id: number; words: string[]; mode: GeneratorMode; options?: WorkerOptions }
+  | { type: "cancel"; id: number };
+
+/** The settings of GeneratorOptions, all optional. */
+export interface WorkerOptions {
+  beam_width?: number;
+  max_nodes?: number;
+  max_millis?: number;
+  anneal_iterations?: number;
+  seed?: number;
+  max_width?: number;
+  max_height?: number;
+  aspect_ratio?: number;
+  scorer?: string;
+  pins?: { word: string; x: number; y: number; orientation: Orientation }[];
+}
+
+/** Posted back by the crossword worker, with the id of the request. */
+export type WorkerResponse =
+  | { type: "progress"; id: number; words_placed: number; candidates_evaluated: number; best_score: number }
+  | { type: "result"; id: number; words: WorkerWord[]; dropped: WorkerDroppedWord[]; cancelled: boolean }
+  | { type: "error"; id: number; message: string };
+
+export interface WorkerWord {
+  word: string;
+  id: number;
+  x: number;
+  y: number;
+  orientation: Orientation;
+}
+
+export interface WorkerDroppedWord {
+  word: string;
+  reason: UnplacedReason;
+  message: string;
+}
+"#;
+
+/// Handles the messages of a crossword worker, see the module documentation.
+#[wasm_bindgen]
+pub struct CrosswordWorker {
+    post_message: js_sys::Function,
+    job: Option<WorkerJob>,
+}
+
+/// A generation started by a "generate" request.
+struct WorkerJob {
+    id: f64,
+    steps: GenerationSteps,
+    options: GeneratorOptions,
+    token: CancellationToken,
+}
+
+#[wasm_bindgen]
+impl CrosswordWorker {
+    /// `post_message` is called with each `WorkerResponse`, usually it is the `postMessage` of the worker.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        #[wasm_bindgen(unchecked_param_type = "(message: WorkerResponse) => void")]
+        post_message: js_sys::Function,
+    ) -> CrosswordWorker {
+        CrosswordWorker {
+            post_message,
+            job: None,
+        }
+    }
+
+    /// Handle a `WorkerRequest`. A bad request is answered with an "error" response.
+    pub fn on_message(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "WorkerRequest")] message: JsValue,
+    ) {
+        let id = get(&message, "id").as_f64().unwrap_or(f64::NAN);
+        if let Err(err) = self.handle(id, &message) {
+            let message = match err.dyn_ref::<js_sys::Error>() {
+                Some(err) => String::from(err.message()),
+                None => err.as_string().unwrap_or_default(),
+            };
+            self.post(object(&[
+                ("type", "error".into()),
+                ("id", id.into()),
+                ("message", message.into()),
+            ]));
+        }
+    }
+
+    /// Generate for about `max_millis`, then return, so the worker can read the next messages. Return true if
+    /// the generation is not finished yet, then call it again.
+    pub fn run(&mut self, max_millis: u32) -> bool {
+        let Some(job) = &mut self.job else {
+            return false;
+        };
+        let started = Instant::now();
+        while started.elapsed() < Duration::from_millis(max_millis.into()) {
+            if job.steps.next().is_none() {
+                let job = self.job.take().unwrap();
+                self.post_result(job);
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl CrosswordWorker {
+    fn handle(&mut self, id: f64, message: &JsValue) -> Result<(), JsValue> {
+        match get(message, "type").as_string().as_deref() {
+            Some("generate") => {
+                let words = parse_words(&get(message, "words"))?;
+                let mode = GeneratorMode::try_from_js_value(get(message, "mode"))
+                    .map_err(|_| JsValue::from_str("bad generator mode"))?;
+                let options = parse_options(&get(message, "options"))?;
+                if let Some(job) = self.job.take() {
+                    job.token.cancel();
+                    self.post_result(job);
+                }
+                self.job = Some(self.start(id, &words, mode, options)?);
+                Ok(())
+            }
+            Some("cancel") => {
+                if let Some(job) = self.job.as_ref().filter(|job| job.id == id) {
+                    job.token.cancel();
+                }
+                Ok(())
+            }
+            _ => Err(JsValue::from_str("bad worker request type")),
+        }
+    }
+
+    fn start(
+        &self,
+        id: f64,
+        words: &[String],
+        mode: GeneratorMode,
+        options: GeneratorOptions,
+    ) -> Result<WorkerJob, JsValue> {
+        let token = CancellationToken::new();
+        let post_message = self.post_message.clone();
+        let generator = options
+            .to_generator(mode)
+            .with_cancellation(token.clone())
+            .with_progress(Box::new(move |progress| {
+                let message = object(&[
+                    ("type", "progress".into()),
+                    ("id", id.into()),
+                    ("words_placed", (progress.words_placed as f64).into()),
+                    (
+                        "candidates_evaluated",
+                        (progress.candidates_evaluated as f64).into(),
+                    ),
+                    ("best_score", progress.best_score.into()),
+                ]);
+                let _ = post_message.call1(&JsValue::NULL, &message);
+            }));
+        let words: Vec<&str> = words.iter().map(|s| s.as_ref()).collect();
+        let steps = generator
+            .into_pinned_steps(&words, options.pins(), mode)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(WorkerJob {
+            id,
+            steps,
+            options,
+            token,
+        })
+    }
+
+    fn post_result(&self, job: WorkerJob) {
+        let report = job.steps.report();
+        let cancelled = job.token.is_cancelled();
+        let layout = if cancelled {
+            report.layout
+        } else {
+            job.options.anneal(report.layout)
+        };
+        let words: js_sys::Array = layout_to_words(&layout).iter().map(word_object).collect();
+        let dropped: js_sys::Array = report
+            .unplaced
+            .iter()
+            .map(|u| dropped_word_object(&DroppedWord::from_unplaced(u)))
+            .collect();
+        self.post(object(&[
+            ("type", "result".into()),
+            ("id", job.id.into()),
+            ("words", words.into()),
+            ("dropped", dropped.into()),
+            ("cancelled", cancelled.into()),
+        ]));
+    }
+
+    fn post(&self, message: JsValue) {
+        if let Err(err) = self.post_message.call1(&JsValue::NULL, &message) {
+            log::warn!(err:?; "cannot post worker message");
+        }
+    }
+}
+
+fn parse_words(value: &JsValue) -> Result<Vec<String>, JsValue> {
+    let words: &js_sys::Array = value
+        .dyn_ref()
+        .ok_or_else(|| JsValue::from_str("words must be an array"))?;
+    words
+        .iter()
+        .map(|word| {
+            word.as_string()
+                .ok_or_else(|| JsValue::from_str("words must be strings"))
+        })
+        .collect()
+}
+
+/// Read the `WorkerOptions` object into [GeneratorOptions].
+fn parse_options(value: &JsValue) -> Result<GeneratorOptions, JsValue> {
+    let mut options = GeneratorOptions::default();
+    if value.is_undefined() || value.is_null() {
+        return Ok(options);
+    }
+    let number = |key: &str| get(value, key).as_f64();
+    options.beam_width = number("beam_width").map(|n| n as usize);
+    options.max_nodes = number("max_nodes").map(|n| n as usize);
+    options.max_millis = number("max_millis").map(|n| n as u32);
+    options.anneal_iterations = number("anneal_iterations").map(|n| n as usize);
+    options.seed = number("seed").map(|n| n as u32);
+    options.max_width = number("max_width").map(|n| n as u32);
+    options.max_height = number("max_height").map(|n| n as u32);
+    options.aspect_ratio = number("aspect_ratio");
+    if let Some(recipe) = get(value, "scorer").as_string() {
+        options.set_scorer(recipe)?;
+    }
+    if let Some(pins) = get(value, "pins").dyn_ref::<js_sys::Array>() {
+        for pin in pins.iter() {
+            let bad_pin = || JsValue::from_str("pins must have word, x, y and orientation");
+            options.pin(
+                get(&pin, "word").as_string().ok_or_else(bad_pin)?,
+                get(&pin, "x").as_f64().ok_or_else(bad_pin)? as i32,
+                get(&pin, "y").as_f64().ok_or_else(bad_pin)? as i32,
+                Orientation::try_from_js_value(get(&pin, "orientation")).map_err(|_| bad_pin())?,
+            );
+        }
+    }
+    Ok(options)
+}
+
+fn word_object(word: &Word) -> JsValue {
+    object(&[
+        ("word", word.word.as_str().into()),
+        ("id", (word.id as f64).into()),
+        ("x", word.x.into()),
+        ("y", word.y.into()),
+        ("orientation", word.orientation.into()),
+    ])
+}
+
+fn dropped_word_object(dropped: &DroppedWord) -> JsValue {
+    object(&[
+        ("word", dropped.word.as_str().into()),
+        ("reason", dropped.reason.into()),
+        ("message", dropped.message.as_str().into()),
+    ])
+}
+
+fn get(object: &JsValue, key: &str) -> JsValue {
+    js_sys::Reflect::get(object, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+}
+
+fn object(fields: &[(&str, JsValue)]) -> JsValue {
+    let object = js_sys::Object::new();
+    for (key, value) in fields {
+        let _ = js_sys::Reflect::set(&object, &JsValue::from_str(key), value);
+    }
+    object.into()
+}
@@ -1,4 +1,8 @@
 mod crossword;
 mod crossword_wasm;
+mod crossword_worker;
+mod logging;
 pub use crossword::*;
 pub use crossword_wasm::*;
+pub use crossword_worker::*;
+pub use logging::*;
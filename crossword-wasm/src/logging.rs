@@ -0,0 +1,24 @@
+//! The library logs with the [log] crate: trace events for each candidate position and layout, and info
+//! summaries of each generation. Nothing is logged until a logger is installed, the command line tool does it
+//! with `--log LEVEL`, and the browser with [crate::set_log_level_js].
+
+use std::fmt::Write;
+
+use log::kv::{self, VisitSource};
+
+/// Format the record as one line, the message followed by the key-value pairs, e.g.
+/// `TRACE crossword::crossword::layout: possible position word=abc pos=XY{0,1} orientation=Ver`.
+pub fn format_log_record(record: &log::Record) -> String {
+    let mut line = format!("{} {}: {}", record.level(), record.target(), record.args());
+    let _ = record.key_values().visit(&mut KeyValueWriter(&mut line));
+    line
+}
+
+struct KeyValueWriter<'a>(&'a mut String);
+
+impl<'kvs> VisitSource<'kvs> for KeyValueWriter<'_> {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let _ = write!(self.0, " {key}={value}");
+        Ok(())
+    }
+}